@@ -0,0 +1,30 @@
+//! Compares `take_array_copy` against the generic `take_array` for 1024 `u8`s.
+//!
+//! Run with `cargo bench`.
+
+use iter_to_array::{ToArray, ToArrayCopy};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 100_000;
+
+fn bench<F: FnMut() -> [u8; 1024]>(name: &str, mut f: F) -> Duration {
+    // warm up
+    for _ in 0..ITERATIONS / 10 {
+        black_box(f());
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    let elapsed = start.elapsed();
+    println!("{:<16} {:>10.1?} per call", name, elapsed / ITERATIONS);
+    elapsed
+}
+
+fn main() {
+    let data: Vec<u8> = (0..=255).cycle().take(1024).collect();
+    let generic = bench("take_array", || black_box(&data).iter().copied().take_array().unwrap());
+    let copy = bench("take_array_copy", || black_box(&data).iter().copied().take_array_copy().unwrap());
+    println!("speedup: {:.2}x", generic.as_secs_f64() / copy.as_secs_f64());
+}
@@ -1,12 +1,51 @@
+//! Traits for collecting iterators into fixed-size arrays.
+//!
+//! # Length errors
+//!
+//! Unless its documentation says otherwise, a method that collects the whole iterator into
+//! an array of size N fails with `ToArrayError::TooShort(got, N)` when the iterator yields
+//! only `got < N` elements, and with `ToArrayError::TooLong(N)` when it yields more than N.
+//! Methods that take from the iterator instead (the `take_*` family) only report `TooShort`,
+//! and leave any further elements in the iterator.
+
 #![no_std]
-#![feature(min_const_generics,maybe_uninit_extra)]
 
-use core::mem::{self, MaybeUninit};
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(any(test, feature = "std"))]
+#[macro_use]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::cell::{Cell, OnceCell};
+use core::cmp::Ordering;
+use core::fmt;
+use core::iter::{FusedIterator, Peekable};
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ops::{Add, Sub};
 
+/// Errors returned when an iterator cannot be collected into an array.
+///
+/// New variants may be added as collectors are added, so matches need a wildcard arm.
 #[derive(Clone,Debug,PartialEq)]
+#[non_exhaustive]
 pub enum ToArrayError {
     TooShort(usize, usize),
-    TooLong(usize)
+    TooLong(usize),
+    NotSorted(usize),
+    Duplicate(usize),
+    InvalidChar(usize, char),
+    SchemaMismatch(usize),
+    BadChecksum,
+    NotAlternating(usize),
+    /// A chunk adapter ran out of elements partway through a chunk.
+    ///
+    /// `offset` is the index in the source iterator at which the incomplete chunk started.
+    TooShortAt { got: usize, expected: usize, offset: usize }
 }
 
 #[derive(Copy,Clone,Debug,PartialEq,Eq)]
@@ -16,6 +55,272 @@ pub enum MaybePartial<A> {
     Empty
 }
 
+#[derive(Clone,Debug,PartialEq)]
+pub enum SumError<T> {
+    Length(ToArrayError),
+    Imbalance(T)
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct ContextError<C> {
+    pub context: C,
+    pub source: ToArrayError
+}
+
+impl fmt::Display for ToArrayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToArrayError::TooShort(got, expected) => write!(f, "iterator too short: got {}, expected {}", got, expected),
+            ToArrayError::TooLong(expected) => write!(f, "iterator too long: expected {}", expected),
+            ToArrayError::NotSorted(index) => write!(f, "element {} is out of order", index),
+            ToArrayError::Duplicate(index) => write!(f, "element {} is a duplicate", index),
+            ToArrayError::InvalidChar(index, c) => write!(f, "invalid character {:?} at index {}", c, index),
+            ToArrayError::SchemaMismatch(index) => write!(f, "element {} does not match the schema", index),
+            ToArrayError::BadChecksum => write!(f, "checksum mismatch"),
+            ToArrayError::NotAlternating(index) => write!(f, "element {} does not alternate in sign", index),
+            ToArrayError::TooShortAt { got, expected, offset } =>
+                write!(f, "iterator too short in chunk at offset {}: got {}, expected {}", offset, got, expected)
+        }
+    }
+}
+
+impl core::error::Error for ToArrayError {}
+
+impl<C: fmt::Display> fmt::Display for ContextError<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.context, self.source)
+    }
+}
+
+impl<C: fmt::Debug + fmt::Display> core::error::Error for ContextError<C> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for SumError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SumError::Length(e) => write!(f, "{}", e),
+            SumError::Imbalance(sum) => write!(f, "sum mismatch: got {}", sum)
+        }
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> core::error::Error for SumError<T> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            SumError::Length(e) => Some(e),
+            SumError::Imbalance(_) => None
+        }
+    }
+}
+
+impl<T> From<ToArrayError> for SumError<T> {
+    fn from(e: ToArrayError) -> Self {
+        SumError::Length(e)
+    }
+}
+
+/// A boxed iterator over the elements left after collecting an array.
+#[cfg(feature = "alloc")]
+pub type BoxedIter<'a, T> = Box<dyn Iterator<Item=T> + 'a>;
+
+/// A run-length encoding as `(value, count)` pairs.
+#[cfg(feature = "alloc")]
+pub type Runs<T> = Vec<(T, usize)>;
+
+/// A pair of indices `(first, second)` into an array, with `first < second`.
+pub type IndexPair = (usize, usize);
+
+/// Array filled one element at a time, without needing a placeholder value for the
+/// slots that are not filled yet.
+///
+/// If the builder is dropped before it is full, the elements pushed so far are dropped.
+///
+/// # Examples
+///
+/// ```
+/// use iter_to_array::*;
+/// let mut builder = ArrayBuilder::<u32, 3>::new();
+/// for i in 0..3 {
+///     builder.push(i * i).unwrap();
+/// }
+/// assert_eq!(builder.push(9), Err(9));
+/// assert_eq!(builder.build().ok(), Some([0, 1, 4]));
+/// ```
+pub struct ArrayBuilder<T, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    len: usize
+}
+
+impl<T, const N: usize> ArrayBuilder<T, N> {
+    pub fn new() -> Self {
+        ArrayBuilder {
+            buf: [const { MaybeUninit::uninit() }; N],
+            len: 0
+        }
+    }
+
+    /// The number of elements pushed so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Append an element, or return it if the array is already full.
+    pub fn push(&mut self, x: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(x);
+        }
+        self.put(x);
+        Ok(())
+    }
+
+    /// Finish the array, or return the builder unchanged if it is not full yet.
+    pub fn build(self) -> Result<[T; N], Self> {
+        if !self.is_full() {
+            return Err(self);
+        }
+        let this = ManuallyDrop::new(self);
+        Ok(unsafe { mem::transmute_copy(&this.buf) })
+    }
+
+    /// Panics if the array is already full.
+    fn put(&mut self, x: T) {
+        self.buf[self.len] = MaybeUninit::new(x);
+        self.len += 1;
+    }
+
+    fn as_slice(&self) -> &[T] {
+        unsafe { &*(&self.buf[..self.len] as *const [MaybeUninit<T>] as *const [T]) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { &mut *(&mut self.buf[..self.len] as *mut [MaybeUninit<T>] as *mut [T]) }
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(unsafe { self.buf[self.len].assume_init_read() })
+    }
+
+    /// Panics if the array is not full.
+    fn into_array(self) -> [T; N] {
+        match self.build() {
+            Ok(arr) => arr,
+            Err(_) => panic!("array is not fully initialized")
+        }
+    }
+
+    fn into_partial_iter(mut self) -> PartialIter<T, N> {
+        // reverse so that popping from the back yields the elements in order
+        self.as_mut_slice().reverse();
+        PartialIter { builder: self }
+    }
+}
+
+impl<T, const N: usize> Default for ArrayBuilder<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayBuilder<T, N> {
+    fn drop(&mut self) {
+        for el in &mut self.buf[..self.len] {
+            unsafe { el.assume_init_drop() };
+        }
+    }
+}
+
+/// Initialized prefix of a caller-owned buffer, dropped if the fill is abandoned.
+struct FillGuard<'a, T> {
+    out: &'a mut [MaybeUninit<T>],
+    len: usize
+}
+
+impl<T> Drop for FillGuard<'_, T> {
+    fn drop(&mut self) {
+        for el in &mut self.out[..self.len] {
+            unsafe { el.assume_init_drop() };
+        }
+    }
+}
+
+/// Take R rows of C elements from the iterator.
+///
+/// If the iterator is too short, returns Err(ToArrayError::TooShort) with the total
+/// number of elements taken, dropping both the completed rows and the partial one.
+fn take_rows<I: Iterator, const R: usize, const C: usize>(iter: &mut I) -> Result<[[I::Item; C]; R], ToArrayError> {
+    let mut taken = 0;
+    let rows = core::iter::from_fn(|| match iter.take_array::<C>() {
+        Ok(row) => {
+            taken += C;
+            Some(row)
+        },
+        Err(e) => {
+            if let ToArrayError::TooShort(got, _) = e {
+                taken += got;
+            }
+            None
+        }
+    }).take_array();
+    rows.map_err(|_| ToArrayError::TooShort(taken, R * C))
+}
+
+/// Take elements from the iterator up to N, handing back the partially filled
+/// builder if the iterator runs out.
+fn take_guarded<I: Iterator, const N: usize>(iter: &mut I) -> Result<[I::Item; N], ArrayBuilder<I::Item, N>> {
+    let mut res = ArrayBuilder::new();
+    while !res.is_full() {
+        match iter.next() {
+            Some(x) => res.put(x),
+            None => return Err(res)
+        }
+    }
+    Ok(res.into_array())
+}
+
+/// Iterator over the elements of an array that could not be filled, in the order they were taken.
+///
+/// Elements that are not iterated over are dropped with the iterator.
+pub struct PartialIter<T, const N: usize> {
+    builder: ArrayBuilder<T, N>,
+}
+
+impl<T, const N: usize> Iterator for PartialIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.builder.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.builder.len, Some(self.builder.len))
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for PartialIter<T, N> {}
+
+/// Elements recovered from a failed `to_array_recover`.
+pub enum Recovered<T, const N: usize> {
+    /// The iterator was too short; holds the elements that were taken.
+    TooShort(PartialIter<T, N>),
+    /// The iterator was too long; holds the full array and the first extra element.
+    TooLong([T; N], T),
+}
+
 pub trait ToArray<T> {
     /// Take elements from the iterator up to N, and collect to an array.
     /// 
@@ -31,7 +336,8 @@ pub trait ToArray<T> {
     /// assert_eq!((0..5).to_array(), Ok([0,1,2,3,4]));
     /// assert_eq!((0..5).to_array::<6>(), Err(ToArrayError::TooShort(5, 6)))
     /// ```
-    /// 
+    ///
+    /// For N = 0, returns Ok([]) without consuming anything.
     fn take_array<const N: usize>(&mut self) -> Result<[T; N], ToArrayError>;
 
     /// Collect the iterator to an array of size N.
@@ -40,77 +346,57 @@ pub trait ToArray<T> {
     /// If the iterator is too long, returns Err(ToArrayError::TooLong).
     /// Otherwise, returns an array of length N.
     ///
+    /// The length is always checked by iterating, since `size_hint` may be wrong.
+    /// See `to_array_exact` for failing early on an `ExactSizeIterator`.
+    /// For N = 0, returns Ok([]) only if the iterator is empty.
     fn to_array<const N: usize>(self) -> Result<[T; N], ToArrayError>;
 
     /// Take elements from the iterator up to N and collect to an array with possible padding.
+    ///
+    /// For N = 0, returns Full([]) without consuming anything.
     fn take_array_partial<F: FnMut() -> T, const N: usize>(&mut self, padding: F) -> MaybePartial<[T; N]>;
+
+    /// Like `take_array_partial`, but `padding` is passed the index of the slot it fills.
+    ///
+    /// `padding` is only called for the slots after the last element taken.
+    fn take_array_partial_indexed<F: FnMut(usize) -> T, const N: usize>(&mut self, padding: F) -> MaybePartial<[T; N]>;
+
+    /// Like `take_array`, but writes the elements into `out` instead of returning an array.
+    ///
+    /// This lets the caller decide where the array lives, avoiding a move of the result.
+    /// On success every slot of `out` is initialized. If the iterator is too short, returns
+    /// Err(ToArrayError::TooShort) after dropping the elements written, so that `out` is
+    /// left entirely uninitialized.
+    fn fill_array<const N: usize>(&mut self, out: &mut [MaybeUninit<T>; N]) -> Result<(), ToArrayError>;
+
+    /// Refill `buf` in place with elements from the iterator, up to N.
+    ///
+    /// Slots are overwritten with `Some(item)` until the iterator runs out,
+    /// and the remaining slots are set to `None`. Returns the number of slots filled.
+    fn fill_optional<const N: usize>(&mut self, buf: &mut [Option<T>; N]) -> usize;
+
+    /// Like `take_array`, but if the iterator is too short the elements already taken
+    /// are handed back along with the error instead of being dropped.
+    fn take_array_recover<const N: usize>(&mut self) -> Result<[T; N], (ToArrayError, PartialIter<T, N>)>;
+
+    /// Like `to_array`, but hands back the elements taken when collection fails.
+    ///
+    /// If the iterator is too short, returns the elements taken so far. If it is
+    /// too long, returns the full array and the first extra element.
+    fn to_array_recover<const N: usize>(self) -> Result<[T; N], Recovered<T, N>>;
+
+    /// Collect the iterator to an array of size N, attaching `ctx` to any error.
+    ///
+    /// Behaves like `to_array`, but on failure returns a `ContextError` holding
+    /// both `ctx` and the underlying `ToArrayError`.
+    fn to_array_context<C, const N: usize>(self, ctx: C) -> Result<[T; N], ContextError<C>>;
 }
 
 impl<I, T: Sized> ToArray<T> for I where I: Iterator<Item=T> {
     fn take_array<const N: usize>(&mut self) -> Result<[T; N], ToArrayError> {
-        let mut res: [MaybeUninit<T>; N] = unsafe {
-            MaybeUninit::uninit().assume_init()
-        };
-        
-        let mut error_index = None;
-        
-        for (i, el) in res.iter_mut().enumerate() {
-            if let Some(x) = self.next() {
-                *el = MaybeUninit::new(x);
-            } else {
-                error_index = Some(i);
-                break;
-            }
-        }
-        
-        if let Some(i) = error_index {
-            // drop initialized elements
-            for el in &mut res[..i] {
-                unsafe { el.assume_init_drop() };
-            } 
-            Err(ToArrayError::TooShort(i, N))
-        } else {
-            Ok(unsafe {
-                mem::transmute_copy(&res)
-            })
-        }
+        take_guarded(self).map_err(|taken| ToArrayError::TooShort(taken.len, N))
     } 
 
-    fn take_array_partial<F: FnMut() -> T, const N: usize>(&mut self, mut padding: F) -> MaybePartial<[T; N]> {
-        let mut res: [MaybeUninit<T>; N] = unsafe {
-            MaybeUninit::uninit().assume_init()
-        };
-        
-        let mut error_index = None;
-        
-        for (i, el) in res.iter_mut().enumerate() {
-            if let Some(x) = self.next() {
-                *el = MaybeUninit::new(x);
-            } else {
-                error_index = Some(i);
-                break;
-            }
-        }
-        
-        if let Some(i) = error_index {
-            if i == 0 {
-                MaybePartial::Empty
-                // no need to uninit anything
-            } else {
-                for el in &mut res[i..] {
-                    *el = MaybeUninit::new(padding())
-                } 
-                MaybePartial::Partial(unsafe {
-                    mem::transmute_copy(&res)
-                }, i)
-            }
-        } else {
-            MaybePartial::Full(unsafe {
-                mem::transmute_copy(&res)
-            })
-        }
-    }
-    
     fn to_array<const N: usize>(mut self) -> Result<[T; N], ToArrayError> {
         let arr = self.take_array()?;
         match self.next() {
@@ -118,188 +404,3539 @@ impl<I, T: Sized> ToArray<T> for I where I: Iterator<Item=T> {
             None => Ok(arr)
         } 
     }
-}
 
-pub trait ToArrayDefault<T> {
-    fn take_array_default<const N: usize>(&mut self) -> [T; N];
-    fn to_array_default<const N: usize>(self) -> Result<[T; N], ToArrayError>;
-}
+    fn take_array_partial<F: FnMut() -> T, const N: usize>(&mut self, mut padding: F) -> MaybePartial<[T; N]> {
+        self.take_array_partial_indexed(|_| padding())
+    }
 
-impl<I, T: Sized + Default> ToArrayDefault<T> for I where I: Iterator<Item=T> {
-    fn take_array_default<const N: usize>(&mut self) -> [T; N] {
-        let mut res: [MaybeUninit<T>; N] = unsafe {
-            MaybeUninit::uninit().assume_init()
-        };
-        
-        for el in &mut res {
-            *el = MaybeUninit::new(self.next().unwrap_or_else(|| Default::default()));
-        }
-        unsafe {
-            mem::transmute_copy(&res)
+    fn take_array_partial_indexed<F: FnMut(usize) -> T, const N: usize>(&mut self, mut padding: F) -> MaybePartial<[T; N]> {
+        match take_guarded(self) {
+            Ok(arr) => MaybePartial::Full(arr),
+            Err(res) if res.len == 0 => MaybePartial::Empty,
+            Err(mut res) => {
+                let taken = res.len;
+                while !res.is_full() {
+                    res.put(padding(res.len));
+                }
+                MaybePartial::Partial(res.into_array(), taken)
+            }
         }
-    } 
-    
-    fn to_array_default<const N: usize>(mut self) -> Result<[T; N], ToArrayError> {
-        let arr = self.take_array_default();
-        match self.next() {
-            Some(_) => Err(ToArrayError::TooLong(N)),
-            None => Ok(arr)
-        } 
     }
-}
 
-pub trait ToArrayPad<T> {
-    fn take_array_pad<const N: usize>(&mut self, pad: T) -> [T; N];
-    fn to_array_pad<const N: usize>(self, pad: T) -> Result<[T; N], ToArrayError>;
-}
+    fn fill_array<const N: usize>(&mut self, out: &mut [MaybeUninit<T>; N]) -> Result<(), ToArrayError> {
+        let mut filled = FillGuard { out, len: 0 };
+        while filled.len < N {
+            match self.next() {
+                Some(x) => {
+                    filled.out[filled.len] = MaybeUninit::new(x);
+                    filled.len += 1;
+                },
+                None => return Err(ToArrayError::TooShort(filled.len, N))
+            }
+        }
+        mem::forget(filled);
+        Ok(())
+    }
 
-impl<I, T: Sized + Clone> ToArrayPad<T> for I where I: Iterator<Item=T> {
-    fn take_array_pad<const N: usize>(&mut self, pad: T) -> [T; N] {
-        let mut res: [MaybeUninit<T>; N] = unsafe {
-            MaybeUninit::uninit().assume_init()
-        };
-        
-        for el in &mut res {
-            *el = MaybeUninit::new(self.next().unwrap_or_else(|| pad.clone()));
+    fn fill_optional<const N: usize>(&mut self, buf: &mut [Option<T>; N]) -> usize {
+        let mut filled = 0;
+        for slot in buf.iter_mut() {
+            *slot = self.next();
+            if slot.is_none() {
+                break;
+            }
+            filled += 1;
         }
-        unsafe {
-            mem::transmute_copy(&res)
+        for slot in &mut buf[filled..] {
+            *slot = None;
         }
-    } 
-    
-    fn to_array_pad<const N: usize>(mut self, pad: T) -> Result<[T; N], ToArrayError> {
-        let arr = self.take_array_pad(pad);
+        filled
+    }
+
+    fn take_array_recover<const N: usize>(&mut self) -> Result<[T; N], (ToArrayError, PartialIter<T, N>)> {
+        take_guarded(self).map_err(|taken| (ToArrayError::TooShort(taken.len, N), taken.into_partial_iter()))
+    }
+
+    fn to_array_recover<const N: usize>(mut self) -> Result<[T; N], Recovered<T, N>> {
+        let arr = take_guarded(&mut self).map_err(|taken| Recovered::TooShort(taken.into_partial_iter()))?;
         match self.next() {
-            Some(_) => Err(ToArrayError::TooLong(N)),
+            Some(extra) => Err(Recovered::TooLong(arr, extra)),
             None => Ok(arr)
-        } 
+        }
     }
-}
 
-pub struct ChunksIter<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> {
-    iter: I,
-    padding: F,
+    fn to_array_context<C, const N: usize>(self, ctx: C) -> Result<[T; N], ContextError<C>> {
+        self.to_array().map_err(|source| ContextError { context: ctx, source })
+    }
 }
 
-impl<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> Iterator for ChunksIter<I, F, N> 
-    where I: Iterator {
-    type Item = [I::Item; N];
+/// Collectors that transform the elements on the way into the array.
+pub trait ToArrayMap<T> {
+    /// Take elements from the iterator up to N, and collect `f(element)` for each to an array.
+    ///
+    /// If the iterator is too short, returns Err(ToArrayError::TooShort) and drops the
+    /// values already produced by `f`.
+    fn map_to_array<U, F: FnMut(T) -> U, const N: usize>(&mut self, f: F) -> Result<[U; N], ToArrayError>;
 
-    fn next(&mut self) -> Option<[I::Item; N]> {
-        let ChunksIter { iter, padding } = self;
-        match iter.take_array_partial(padding) {
-            MaybePartial::Empty => None,
-            MaybePartial::Partial(x, _) => Some(x),
-            MaybePartial::Full(x) => Some(x)
+    /// Take elements from the iterator up to N, and collect `f(index, element)` for each to an array.
+    ///
+    /// If the iterator is too short, returns Err(ToArrayError::TooShort).
+    fn take_array_enumerate_map<U, F: FnMut(usize, T) -> U, const N: usize>(&mut self, f: F) -> Result<[U; N], ToArrayError>;
+
+    /// Build an array of size N by repeatedly calling `f` with a mutable state and the next element.
+    ///
+    /// `f` receives `None` once the iterator is exhausted, and can keep producing values
+    /// from its state. If `f` returns `None` before N values are produced,
+    /// returns Err(ToArrayError::TooShort).
+    fn unfold_to_array<S, U, F, const N: usize>(&mut self, state: S, f: F) -> Result<[U; N], ToArrayError>
+        where F: FnMut(&mut S, Option<T>) -> Option<U>;
+
+    /// Take elements from the iterator into the even indices of an array of size N,
+    /// filling each odd index `i` with `sep(i)`.
+    ///
+    /// If the iterator runs out before the array is filled, returns Err(ToArrayError::TooShort)
+    /// with the number of slots filled.
+    fn take_array_join<S: Fn(usize) -> T, const N: usize>(&mut self, sep: S) -> Result<[T; N], ToArrayError>;
+
+    /// Take elements from the iterator until N of them match `predicate`, and collect their positions to an array.
+    ///
+    /// Positions are counted from the first element taken by this call. The elements
+    /// themselves are dropped.
+    /// If fewer than N elements match, returns Err(ToArrayError::TooShort).
+    fn take_index_array<P: FnMut(&T) -> bool, const N: usize>(&mut self, predicate: P) -> Result<[usize; N], ToArrayError>;
+}
+
+impl<I, T: Sized> ToArrayMap<T> for I where I: Iterator<Item=T> {
+    fn map_to_array<U, F: FnMut(T) -> U, const N: usize>(&mut self, mut f: F) -> Result<[U; N], ToArrayError> {
+        let mut res = ArrayBuilder::new();
+        while !res.is_full() {
+            match self.next() {
+                Some(x) => res.put(f(x)),
+                None => return Err(ToArrayError::TooShort(res.len, N))
+            }
         }
+        Ok(res.into_array())
     }
-}
 
-pub trait Chunks: Iterator + Sized {
-    fn chunks<F: FnMut() -> <Self as Iterator>::Item, const N: usize>(self, padding: F) -> ChunksIter<Self, F, N> {
-        ChunksIter { iter: self, padding }
+    fn take_array_enumerate_map<U, F: FnMut(usize, T) -> U, const N: usize>(&mut self, mut f: F) -> Result<[U; N], ToArrayError> {
+        self.by_ref().enumerate().map(|(i, x)| f(i, x)).take_array()
     }
-}
 
-impl<I> Chunks for I where I: Iterator + Sized {}
+    fn unfold_to_array<S, U, F, const N: usize>(&mut self, mut state: S, mut f: F) -> Result<[U; N], ToArrayError>
+        where F: FnMut(&mut S, Option<T>) -> Option<U> {
+        core::iter::from_fn(|| f(&mut state, self.next())).take_array()
+    }
 
-pub trait ChunksDefault: Iterator + Sized {
-    fn chunks_default<const N: usize>(self) -> ChunksIter<Self, fn() -> <Self as Iterator>::Item, N>;
-}
+    fn take_array_join<S: Fn(usize) -> T, const N: usize>(&mut self, sep: S) -> Result<[T; N], ToArrayError> {
+        let mut i = 0;
+        core::iter::from_fn(|| {
+            let x = if i % 2 == 0 { self.next()? } else { sep(i) };
+            i += 1;
+            Some(x)
+        }).take_array()
+    }
 
-impl<I> ChunksDefault for I where I: Iterator, <I as Iterator>::Item: Default {
-    fn chunks_default<const N: usize>(self) -> ChunksIter<Self, fn() -> <Self as Iterator>::Item, N> {
-        ChunksIter { iter: self, padding: Default::default }
+    fn take_index_array<P: FnMut(&T) -> bool, const N: usize>(&mut self, mut predicate: P) -> Result<[usize; N], ToArrayError> {
+        self.by_ref().enumerate().filter(|(_, x)| predicate(x)).map(|(i, _)| i).take_array()
     }
 }
 
-#[cfg(test)]
-#[macro_use]
-extern crate std;
+/// Collectors that choose, check or classify elements through a closure.
+pub trait ToArraySelect<T> {
+    /// Collect the first element for each distinct key into an array of size N.
+    ///
+    /// Elements whose key equals the key of an already collected element are dropped.
+    /// `key` is called once per element taken, and collection stops after the N-th
+    /// distinct key without looking at the rest of the iterator.
+    /// If there are fewer than N distinct keys, returns Err(ToArrayError::TooShort).
+    fn to_array_unique_by<K: PartialEq, F: FnMut(&T) -> K, const N: usize>(self, key: F) -> Result<[T; N], ToArrayError>;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Scan the whole iterator, keeping the N elements with the largest keys, and
+    /// return them sorted by key in descending order.
+    ///
+    /// Elements that are pushed out are dropped as the scan goes. The order of elements
+    /// with equal keys is unspecified.
+    /// If the iterator has fewer than N elements, returns Err(ToArrayError::TooShort).
+    fn top_n_array_by_key<K: Ord, F: FnMut(&T) -> K, const N: usize>(self, key: F) -> Result<[T; N], ToArrayError>;
 
-    #[test]
-    fn to_array() {
-        assert_eq!((0..5).to_array(), Ok([0,1,2,3,4]));
-        assert_eq!((0..5).to_array::<10>(), Err(ToArrayError::TooShort(5, 10)));
-        assert_eq!((0..0).to_array::<10>(), Err(ToArrayError::TooShort(0, 10)));
-        assert_eq!((0..5).to_array::<4>(), Err(ToArrayError::TooLong(4)));
+    /// Collect the iterator to an array of size N, checking element `i` against `schema[i]`.
+    ///
+    /// Collection stops at the first element rejected by its check, returning
+    /// Err(ToArrayError::SchemaMismatch) with its index.
+    fn to_array_schema<const N: usize>(self, schema: [fn(&T) -> bool; N]) -> Result<[T; N], ToArrayError>;
 
-        let mut iter = 0..10;
-        assert_eq!(iter.take_array(), Ok([0,1,2,3,4]));
-        assert_eq!(iter.take_array(), Ok([5,6,7]));
-        assert_eq!(iter.take_array::<5>(), Err(ToArrayError::TooShort(2, 5)));
-    }
+    /// Collect the iterator to an array of size N, counting how many elements fall into each of D buckets.
+    ///
+    /// `index_of` gives the bucket of each element. Elements whose bucket index is
+    /// D or greater are not counted.
+    fn to_array_counts<F: Fn(&T) -> usize, const N: usize, const D: usize>(self, index_of: F) -> Result<([T; N], [usize; D]), ToArrayError>;
+}
 
-    #[test]
-    fn to_array_default() {
-        assert_eq!((0..5).to_array_default(), Ok([0,1,2,3,4]));
-        assert_eq!((0..5).to_array_default(), Ok([0,1,2,3,4,0,0]));
-        assert_eq!((0..0).to_array_default(), Ok([0; 10]));
-        assert_eq!((0..5).to_array_default::<4>(), Err(ToArrayError::TooLong(4)));
+impl<I, T: Sized> ToArraySelect<T> for I where I: Iterator<Item=T> {
+    fn to_array_unique_by<K: PartialEq, F: FnMut(&T) -> K, const N: usize>(mut self, mut key: F) -> Result<[T; N], ToArrayError> {
+        let mut res = ArrayBuilder::<T, N>::new();
+        let mut keys = ArrayBuilder::<K, N>::new();
 
-        let mut iter = 0..8;
+        while !res.is_full() {
+            match self.next() {
+                Some(x) => {
+                    let k = key(&x);
+                    if !keys.as_slice().contains(&k) {
+                        keys.put(k);
+                        res.put(x);
+                    }
+                },
+                None => return Err(ToArrayError::TooShort(res.len, N))
+            }
+        }
+
+        Ok(res.into_array())
+    }
+
+    fn top_n_array_by_key<K: Ord, F: FnMut(&T) -> K, const N: usize>(mut self, mut key: F) -> Result<[T; N], ToArrayError> {
+        let mut heap: [(K, T); N] = self.by_ref().map(|x| (key(&x), x)).take_array()?;
+        for i in (0..N / 2).rev() {
+            sift_down_min(&mut heap, i);
+        }
+
+        for x in self {
+            let k = key(&x);
+            if heap.first().is_some_and(|(min, _)| k > *min) {
+                heap[0] = (k, x);
+                sift_down_min(&mut heap, 0);
+            }
+        }
+
+        // heap sort: moving the minimum to the back leaves the largest key in front
+        for end in (1..N).rev() {
+            heap.swap(0, end);
+            sift_down_min(&mut heap[..end], 0);
+        }
+        Ok(heap.map(|(_, x)| x))
+    }
+
+    fn to_array_schema<const N: usize>(mut self, schema: [fn(&T) -> bool; N]) -> Result<[T; N], ToArrayError> {
+        let arr = collect_validated(&mut self, |prefix, x| if schema[prefix.len()](x) {
+            Ok(true)
+        } else {
+            Err(ToArrayError::SchemaMismatch(prefix.len()))
+        })?;
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(N)),
+            None => Ok(arr)
+        }
+    }
+
+    fn to_array_counts<F: Fn(&T) -> usize, const N: usize, const D: usize>(self, index_of: F) -> Result<([T; N], [usize; D]), ToArrayError> {
+        let arr = self.to_array()?;
+        let mut counts = [0; D];
+        for x in &arr {
+            if let Some(count) = counts.get_mut(index_of(x)) {
+                *count += 1;
+            }
+        }
+        Ok((arr, counts))
+    }
+}
+
+/// Collectors for two-dimensional arrays.
+pub trait ToArrayGrid<T> {
+    /// Collect the iterator to an R by C grid, filling it row by row.
+    ///
+    /// The grid counts as an array of R * C elements for length errors.
+    fn to_array_2d<const R: usize, const C: usize>(self) -> Result<[[T; C]; R], ToArrayError>;
+
+    /// Collect the iterator to an R by C grid, filling it column by column.
+    ///
+    /// The first R elements become column 0, the next R become column 1, and so on.
+    /// The grid counts as an array of R * C elements for length errors.
+    fn to_grid_col_major<const R: usize, const C: usize>(self) -> Result<[[T; C]; R], ToArrayError>;
+}
+
+impl<I, T: Sized> ToArrayGrid<T> for I where I: Iterator<Item=T> {
+    fn to_array_2d<const R: usize, const C: usize>(mut self) -> Result<[[T; C]; R], ToArrayError> {
+        let rows = take_rows(&mut self)?;
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(R * C)),
+            None => Ok(rows)
+        }
+    }
+
+    fn to_grid_col_major<const R: usize, const C: usize>(mut self) -> Result<[[T; C]; R], ToArrayError> {
+        let cols: [[T; R]; C] = take_rows(&mut self)?;
+        if self.next().is_some() {
+            return Err(ToArrayError::TooLong(R * C));
+        }
+        let mut cols = cols.map(IntoIterator::into_iter);
+        Ok(core::array::from_fn(|_| core::array::from_fn(|c| cols[c].next().expect("column has R elements"))))
+    }
+}
+
+/// Collectors that return the array together with the rest of the iterator.
+pub trait ToArraySplit<T> {
+    /// Take the first N elements into an array, and return it with the rest of the iterator.
+    ///
+    /// If the iterator is too short, returns Err(ToArrayError::TooShort).
+    fn parse_array<const N: usize>(self) -> Result<([T; N], Self), ToArrayError> where Self: Sized;
+
+    /// Take the first N elements into an array, and return it with a boxed iterator over the rest.
+    ///
+    /// If the iterator is too short, returns Err(ToArrayError::TooShort).
+    #[cfg(feature = "alloc")]
+    fn split_array_boxed<'a, const N: usize>(self) -> Result<([T; N], BoxedIter<'a, T>), ToArrayError> where Self: 'a;
+
+    /// Take the first N elements into an array, and return it with a closure that drains
+    /// the rest of the iterator into a `Vec` when called.
+    ///
+    /// Nothing past the first N elements is pulled unless the closure is called.
+    /// If the iterator is too short, returns Err(ToArrayError::TooShort).
+    #[cfg(feature = "alloc")]
+    fn to_array_lazy_tail<const N: usize>(self) -> Result<([T; N], impl FnOnce() -> Vec<T>), ToArrayError>;
+
+    /// Like `to_array`, but hands back the elements that did not fit in a `Vec`.
+    ///
+    /// If the iterator is too short, the `Vec` holds the elements taken. If it is too long,
+    /// the first N elements are dropped and the `Vec` holds the rest of the iterator.
+    #[cfg(feature = "alloc")]
+    fn to_array_overflow<const N: usize>(self) -> Result<[T; N], (ToArrayError, Vec<T>)>;
+}
+
+impl<I, T: Sized> ToArraySplit<T> for I where I: Iterator<Item=T> {
+    fn parse_array<const N: usize>(mut self) -> Result<([T; N], Self), ToArrayError> {
+        let arr = self.take_array()?;
+        Ok((arr, self))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn split_array_boxed<'a, const N: usize>(mut self) -> Result<([T; N], BoxedIter<'a, T>), ToArrayError> where Self: 'a {
+        let arr = self.take_array()?;
+        Ok((arr, Box::new(self)))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn to_array_lazy_tail<const N: usize>(mut self) -> Result<([T; N], impl FnOnce() -> Vec<T>), ToArrayError> {
+        let arr = self.take_array()?;
+        Ok((arr, move || self.collect()))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn to_array_overflow<const N: usize>(mut self) -> Result<[T; N], (ToArrayError, Vec<T>)> {
+        let arr = self.take_array_recover().map_err(|(e, taken)| (e, taken.collect()))?;
+        match self.next() {
+            Some(extra) => Err((ToArrayError::TooLong(N), core::iter::once(extra).chain(self).collect())),
+            None => Ok(arr)
+        }
+    }
+}
+
+/// Collectors that clone elements of the array.
+pub trait ToArrayClone<T> {
+    /// Collect the iterator to an array of size N, along with an iterator over each element
+    /// paired with the next one, i.e. `(arr[i], arr[i + 1])` for i in 0..N-1.
+    fn to_array_pairs<const N: usize>(self) -> Result<([T; N], impl Iterator<Item=(T, T)>), ToArrayError>;
+}
+
+impl<I, T: Clone> ToArrayClone<T> for I where I: Iterator<Item=T> {
+    fn to_array_pairs<const N: usize>(self) -> Result<([T; N], impl Iterator<Item=(T, T)>), ToArrayError> {
+        let arr: [T; N] = self.to_array()?;
+        let mut rest = IntoIterator::into_iter(arr.clone());
+        let first = rest.next();
+        let pairs = rest.scan(first, |prev, x| prev.replace(x.clone()).map(|p| (p, x)));
+        Ok((arr, pairs))
+    }
+}
+
+#[cfg(feature = "std")]
+/// Collectors that need the `std` feature.
+pub trait ToArrayStd<T> {
+    /// Take elements from the iterator up to N, calling `std::thread::yield_now()`
+    /// after every `yield_every` elements.
+    ///
+    /// A `yield_every` of 0 never yields. Otherwise behaves exactly like `take_array`.
+    fn take_array_yielding<const N: usize>(&mut self, yield_every: usize) -> Result<[T; N], ToArrayError>;
+
+    /// Collect the iterator to an array of size N, along with the time at which collection completed.
+    fn to_array_timestamped<const N: usize>(self) -> Result<([T; N], std::time::SystemTime), ToArrayError>;
+}
+
+#[cfg(feature = "std")]
+impl<I, T: Sized> ToArrayStd<T> for I where I: Iterator<Item=T> {
+    fn take_array_yielding<const N: usize>(&mut self, yield_every: usize) -> Result<[T; N], ToArrayError> {
+        take_array_with_yield(self, yield_every, std::thread::yield_now)
+    }
+
+    fn to_array_timestamped<const N: usize>(self) -> Result<([T; N], std::time::SystemTime), ToArrayError> {
+        let arr = self.to_array()?;
+        Ok((arr, std::time::SystemTime::now()))
+    }
+}
+
+#[cfg(feature = "log")]
+/// Collectors that need the `log` feature.
+pub trait ToArrayLog<T> {
+    /// Like `take_array`, but logs a trace record for each element placed and
+    /// a debug record when the collection completes or fails.
+    fn take_array_logged<const N: usize>(&mut self) -> Result<[T; N], ToArrayError>;
+}
+
+#[cfg(feature = "log")]
+impl<I, T: Sized> ToArrayLog<T> for I where I: Iterator<Item=T> {
+    fn take_array_logged<const N: usize>(&mut self) -> Result<[T; N], ToArrayError> {
+        let res = self.by_ref().enumerate().map(|(i, x)| {
+            log::trace!("take_array: placed element {} of {}", i, N);
+            x
+        }).take_array();
+        match &res {
+            Ok(_) => log::debug!("take_array: collected {} elements", N),
+            Err(e) => log::debug!("take_array: failed with {:?}", e)
+        }
+        res
+    }
+}
+
+pub trait ToArrayCopy<T> {
+    /// Same as `take_array`, specialized for `Copy` elements.
+    ///
+    /// `Copy` types never need dropping, so the fill skips the bookkeeping needed to clean
+    /// up a partially filled array.
+    fn take_array_copy<const N: usize>(&mut self) -> Result<[T; N], ToArrayError>;
+}
+
+impl<I, T: Copy> ToArrayCopy<T> for I where I: Iterator<Item=T> {
+    fn take_array_copy<const N: usize>(&mut self) -> Result<[T; N], ToArrayError> {
+        let mut res = [MaybeUninit::<T>::uninit(); N];
+        for (i, el) in res.iter_mut().enumerate() {
+            match self.next() {
+                Some(x) => *el = MaybeUninit::new(x),
+                None => return Err(ToArrayError::TooShort(i, N))
+            }
+        }
+        Ok(unsafe { mem::transmute_copy(&res) })
+    }
+}
+
+pub trait ToArrayDefault<T> {
+    fn take_array_default<const N: usize>(&mut self) -> [T; N];
+    fn to_array_default<const N: usize>(self) -> Result<[T; N], ToArrayError>;
+
+    /// Like `take_array_default`, but also returns how many elements came from the iterator.
+    fn take_array_counted<const N: usize>(&mut self) -> ([T; N], usize);
+
+    /// Take elements from the iterator up to N or up to a boundary element, padding with defaults.
+    ///
+    /// The boundary element is consumed and dropped. Returns `Partial` with the number of
+    /// elements taken if stopped early (at a boundary or at the end of the iterator),
+    /// and `Empty` only if the iterator was exhausted before taking anything.
+    fn take_array_paragraph<B: FnMut(&T) -> bool, const N: usize>(&mut self, is_boundary: B) -> MaybePartial<[T; N]>;
+
+    /// Take elements from the iterator up to N, then fill the remaining slots from `pad`.
+    ///
+    /// Returns `Full` if the iterator and `pad` together fill the array, even if the iterator
+    /// was empty. If `pad` returns `None` first, the rest of the array is filled with defaults
+    /// and `Partial` is returned with the number of elements taken from the iterator. If the
+    /// iterator was empty and `pad` ran out, returns `Empty` and drops any padding taken.
+    fn take_array_try_pad<F: FnMut() -> Option<T>, const N: usize>(&mut self, pad: F) -> MaybePartial<[T; N]>;
+}
+
+impl<I, T: Sized + Default> ToArrayDefault<T> for I where I: Iterator<Item=T> {
+    fn take_array_default<const N: usize>(&mut self) -> [T; N] {
+        let mut res = ArrayBuilder::new();
+        while !res.is_full() {
+            res.put(self.next().unwrap_or_else(|| Default::default()));
+        }
+        res.into_array()
+    } 
+    
+    fn take_array_counted<const N: usize>(&mut self) -> ([T; N], usize) {
+        match self.take_array_partial(Default::default) {
+            MaybePartial::Full(arr) => (arr, N),
+            MaybePartial::Partial(arr, taken) => (arr, taken),
+            MaybePartial::Empty => (core::array::from_fn(|_| T::default()), 0)
+        }
+    }
+
+    fn to_array_default<const N: usize>(mut self) -> Result<[T; N], ToArrayError> {
+        let arr = self.take_array_default();
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(N)),
+            None => Ok(arr)
+        } 
+    }
+
+    fn take_array_paragraph<B: FnMut(&T) -> bool, const N: usize>(&mut self, mut is_boundary: B) -> MaybePartial<[T; N]> {
+        let mut at_boundary = false;
+        let res = self.by_ref().take_while(|x| {
+            at_boundary = is_boundary(x);
+            !at_boundary
+        }).take_array_partial(Default::default);
+        match res {
+            MaybePartial::Empty if at_boundary => MaybePartial::Partial(core::array::from_fn(|_| T::default()), 0),
+            res => res
+        }
+    }
+
+    fn take_array_try_pad<F: FnMut() -> Option<T>, const N: usize>(&mut self, mut pad: F) -> MaybePartial<[T; N]> {
+        let mut res = match take_guarded(self) {
+            Ok(arr) => return MaybePartial::Full(arr),
+            Err(res) => res
+        };
+        let taken = res.len;
+        while !res.is_full() {
+            match pad() {
+                Some(x) => res.put(x),
+                None => break
+            }
+        }
+        if res.is_full() {
+            return MaybePartial::Full(res.into_array());
+        }
+        if taken == 0 {
+            return MaybePartial::Empty;
+        }
+        while !res.is_full() {
+            res.put(T::default());
+        }
+        MaybePartial::Partial(res.into_array(), taken)
+    }
+}
+
+pub trait ToArrayPad<T> {
+    fn take_array_pad<const N: usize>(&mut self, pad: T) -> [T; N];
+    fn to_array_pad<const N: usize>(self, pad: T) -> Result<[T; N], ToArrayError>;
+
+    /// Like `take_array_pad`, but takes the pad by reference.
+    ///
+    /// `pad` is cloned once for each missing slot, and never if the iterator fills the array.
+    fn take_array_pad_ref<const N: usize>(&mut self, pad: &T) -> [T; N];
+
+    /// Like `take_array_pad`, but also returns a mask where bit `i` is set if slot `i` was padded.
+    ///
+    /// N must be at most 64.
+    fn take_array_pad_masked<const N: usize>(&mut self, pad: T) -> ([T; N], u64);
+}
+
+impl<I, T: Sized + Clone> ToArrayPad<T> for I where I: Iterator<Item=T> {
+    fn take_array_pad<const N: usize>(&mut self, pad: T) -> [T; N] {
+        self.take_array_pad_ref(&pad)
+    } 
+
+    fn take_array_pad_ref<const N: usize>(&mut self, pad: &T) -> [T; N] {
+        match take_guarded(self) {
+            Ok(arr) => arr,
+            Err(mut res) => {
+                while !res.is_full() {
+                    res.put(pad.clone());
+                }
+                res.into_array()
+            }
+        }
+    }
+    
+    fn to_array_pad<const N: usize>(mut self, pad: T) -> Result<[T; N], ToArrayError> {
+        let arr = self.take_array_pad(pad);
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(N)),
+            None => Ok(arr)
+        } 
+    }
+
+    fn take_array_pad_masked<const N: usize>(&mut self, pad: T) -> ([T; N], u64) {
+        const { assert!(N <= 64, "take_array_pad_masked supports at most 64 elements") };
+        let mut mask = 0;
+        let mut exhausted = false;
+        let arr = core::array::from_fn(|i| {
+            if !exhausted {
+                if let Some(x) = self.next() {
+                    return x;
+                }
+                exhausted = true;
+            }
+            mask |= 1 << i;
+            pad.clone()
+        });
+        (arr, mask)
+    }
+}
+
+pub trait ToArraySum<T> {
+    /// Collect the iterator to an array of size N, checking that its elements sum to `expected`.
+    ///
+    /// If the iterator length is not N, returns Err(SumError::Length).
+    /// If the sum differs from `expected`, returns Err(SumError::Imbalance) with the actual sum.
+    fn to_array_checked_sum<const N: usize>(self, expected: T) -> Result<[T; N], SumError<T>>;
+}
+
+impl<I, T> ToArraySum<T> for I where I: Iterator<Item=T>, T: Add<Output=T> + PartialEq + Copy + Default {
+    fn to_array_checked_sum<const N: usize>(self, expected: T) -> Result<[T; N], SumError<T>> {
+        let arr: [T; N] = self.to_array()?;
+        let sum = arr.iter().fold(T::default(), |acc, &x| acc + x);
+        if sum == expected {
+            Ok(arr)
+        } else {
+            Err(SumError::Imbalance(sum))
+        }
+    }
+}
+
+/// An iterator that is collected into an array of size N on first access.
+///
+/// The result of the collection (either the array or the error) is cached,
+/// so the iterator is consumed at most once. Only needs `core`, so no feature is required.
+pub struct LazyArray<T, const N: usize, I: Iterator<Item=T>> {
+    iter: Cell<Option<I>>,
+    value: OnceCell<Result<[T; N], ToArrayError>>,
+}
+
+impl<T, const N: usize, I: Iterator<Item=T>> LazyArray<T, N, I> {
+    pub fn new(iter: I) -> Self {
+        LazyArray { iter: Cell::new(Some(iter)), value: OnceCell::new() }
+    }
+
+    /// Get the collected array, collecting the iterator with `to_array` if
+    /// this is the first call.
+    pub fn get(&self) -> Result<&[T; N], &ToArrayError> {
+        self.value.get_or_init(|| {
+            self.iter.take().expect("LazyArray iterator already consumed").to_array()
+        }).as_ref()
+    }
+}
+
+pub trait ToArrayFlatten<T> {
+    /// Collect the `Some` values of the iterator to an array of size N, skipping `None`s.
+    ///
+    /// If there are fewer than N `Some` values, returns Err(ToArrayError::TooShort).
+    /// If there are more than N `Some` values, returns Err(ToArrayError::TooLong).
+    fn to_array_flatten<const N: usize>(self) -> Result<[T; N], ToArrayError>;
+}
+
+impl<I, T> ToArrayFlatten<T> for I where I: Iterator<Item=Option<T>> {
+    fn to_array_flatten<const N: usize>(self) -> Result<[T; N], ToArrayError> {
+        self.flatten().to_array()
+    }
+}
+
+pub trait ToArrayOrd<T> {
+    /// Collect the iterator to an array of size N, replacing each element with
+    /// the minimum of the elements up to and including it.
+    fn to_running_min_array<const N: usize>(self) -> Result<[T; N], ToArrayError> where T: Copy;
+
+    /// Collect the iterator to an array of size N, replacing each element with
+    /// the maximum of the elements up to and including it.
+    fn to_running_max_array<const N: usize>(self) -> Result<[T; N], ToArrayError> where T: Copy;
+
+    /// Collect the iterator to an array of size N, along with the indices that would sort it.
+    ///
+    /// The array is returned in its original order. The index array lists the positions
+    /// of the elements in ascending order, with ties kept in their original order.
+    fn to_array_with_order<const N: usize>(self) -> Result<([T; N], [usize; N]), ToArrayError>;
+
+    /// Collect the iterator to an array of size N, clamping each element into `[min, max]`.
+    ///
+    /// Panics if `min > max`, like `Ord::clamp`.
+    fn to_array_clamped<const N: usize>(self, min: T, max: T) -> Result<[T; N], ToArrayError> where T: Copy;
+
+    /// Collect the iterator to an array of size N, along with the rank of each element.
+    ///
+    /// `ranks[i]` is the position element `i` would have if the array were sorted
+    /// in ascending order, with ties ranked by their original order. This is the
+    /// inverse of the permutation returned by `to_array_with_order`.
+    fn to_array_ranks<const N: usize>(self) -> Result<([T; N], [usize; N]), ToArrayError>;
+
+    /// Collect the iterator to an array of size N, along with its distinct values in ascending order.
+    ///
+    /// The array keeps the original order.
+    #[cfg(feature = "alloc")]
+    fn to_array_with_sorted_unique<const N: usize>(self) -> Result<([T; N], Vec<T>), ToArrayError> where T: Clone;
+}
+
+impl<I, T: Ord> ToArrayOrd<T> for I where I: Iterator<Item=T> {
+    fn to_running_min_array<const N: usize>(self) -> Result<[T; N], ToArrayError> where T: Copy {
+        let mut arr: [T; N] = self.to_array()?;
+        for i in 1..N {
+            arr[i] = arr[i].min(arr[i - 1]);
+        }
+        Ok(arr)
+    }
+
+    fn to_running_max_array<const N: usize>(self) -> Result<[T; N], ToArrayError> where T: Copy {
+        let mut arr: [T; N] = self.to_array()?;
+        for i in 1..N {
+            arr[i] = arr[i].max(arr[i - 1]);
+        }
+        Ok(arr)
+    }
+
+    fn to_array_with_order<const N: usize>(self) -> Result<([T; N], [usize; N]), ToArrayError> {
+        let arr: [T; N] = self.to_array()?;
+        let mut order: [usize; N] = core::array::from_fn(|i| i);
+        order.sort_unstable_by(|&a, &b| arr[a].cmp(&arr[b]).then(a.cmp(&b)));
+        Ok((arr, order))
+    }
+
+    fn to_array_clamped<const N: usize>(self, min: T, max: T) -> Result<[T; N], ToArrayError> where T: Copy {
+        self.map(|x| x.clamp(min, max)).to_array()
+    }
+
+    fn to_array_ranks<const N: usize>(self) -> Result<([T; N], [usize; N]), ToArrayError> {
+        let (arr, order) = self.to_array_with_order()?;
+        let mut ranks = [0; N];
+        for (rank, &i) in order.iter().enumerate() {
+            ranks[i] = rank;
+        }
+        Ok((arr, ranks))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn to_array_with_sorted_unique<const N: usize>(self) -> Result<([T; N], Vec<T>), ToArrayError> where T: Clone {
+        let arr: [T; N] = self.to_array()?;
+        let mut unique = arr.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+        Ok((arr, unique))
+    }
+}
+
+#[cfg(feature = "ndarray")]
+pub trait ToNdarray<T> {
+    /// Collect the iterator to a one-dimensional `ndarray` of length N.
+    fn to_ndarray1<const N: usize>(self) -> Result<ndarray::Array1<T>, ToArrayError>;
+
+    /// Collect the iterator to an R by C `ndarray` matrix, filling it row by row.
+    ///
+    /// The grid counts as an array of R * C elements for length errors.
+    fn to_ndarray2<const R: usize, const C: usize>(self) -> Result<ndarray::Array2<T>, ToArrayError>;
+}
+
+#[cfg(feature = "ndarray")]
+fn collect_ndarray1<I: Iterator>(mut iter: I, len: usize) -> Result<ndarray::Array1<I::Item>, ToArrayError> {
+    let arr: ndarray::Array1<I::Item> = iter.by_ref().take(len).collect();
+    if arr.len() < len {
+        Err(ToArrayError::TooShort(arr.len(), len))
+    } else if iter.next().is_some() {
+        Err(ToArrayError::TooLong(len))
+    } else {
+        Ok(arr)
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<I, T> ToNdarray<T> for I where I: Iterator<Item=T> {
+    fn to_ndarray1<const N: usize>(self) -> Result<ndarray::Array1<T>, ToArrayError> {
+        collect_ndarray1(self, N)
+    }
+
+    fn to_ndarray2<const R: usize, const C: usize>(self) -> Result<ndarray::Array2<T>, ToArrayError> {
+        let arr = collect_ndarray1(self, R * C)?;
+        Ok(arr.into_shape_with_order((R, C)).expect("array has R * C elements"))
+    }
+}
+
+pub trait BitIterExt {
+    /// Take 8 * N bits from the iterator and pack them into N bytes, most significant bit first.
+    ///
+    /// If the iterator has fewer than 8 * N bits, returns Err(ToArrayError::TooShort)
+    /// with the number of bits taken and 8 * N.
+    fn take_bits_array<const N: usize>(&mut self) -> Result<[u8; N], ToArrayError>;
+
+    /// Take 8 * N bits from the iterator and pack them into N bytes, least significant bit first.
+    ///
+    /// Errors are the same as for `take_bits_array`.
+    fn take_bits_array_lsb<const N: usize>(&mut self) -> Result<[u8; N], ToArrayError>;
+}
+
+fn take_bits<I: Iterator<Item=bool>, const N: usize>(iter: &mut I, msb_first: bool) -> Result<[u8; N], ToArrayError> {
+    let mut res = [0u8; N];
+    for i in 0..8 * N {
+        match iter.next() {
+            Some(true) => res[i / 8] |= if msb_first { 0x80 >> (i % 8) } else { 1 << (i % 8) },
+            Some(false) => {},
+            None => return Err(ToArrayError::TooShort(i, 8 * N))
+        }
+    }
+    Ok(res)
+}
+
+impl<I> BitIterExt for I where I: Iterator<Item=bool> {
+    fn take_bits_array<const N: usize>(&mut self) -> Result<[u8; N], ToArrayError> {
+        take_bits(self, true)
+    }
+
+    fn take_bits_array_lsb<const N: usize>(&mut self) -> Result<[u8; N], ToArrayError> {
+        take_bits(self, false)
+    }
+}
+
+/// Take elements from the iterator up to N, calling `yield_fn` after every `every` elements.
+#[cfg(feature = "std")]
+fn take_array_with_yield<I: Iterator, F: FnMut(), const N: usize>(iter: &mut I, every: usize, mut yield_fn: F) -> Result<[I::Item; N], ToArrayError> {
+    iter.by_ref().enumerate().map(|(i, x)| {
+        if every != 0 && (i + 1) % every == 0 {
+            yield_fn();
+        }
+        x
+    }).take_array()
+}
+
+/// Restore the min-heap property (by key) for the subtree rooted at `i`.
+fn sift_down_min<K: Ord, T>(heap: &mut [(K, T)], mut i: usize) {
+    loop {
+        let mut smallest = i;
+        for child in [2 * i + 1, 2 * i + 2] {
+            if child < heap.len() && heap[child].0 < heap[smallest].0 {
+                smallest = child;
+            }
+        }
+        if smallest == i {
+            return;
+        }
+        heap.swap(i, smallest);
+        i = smallest;
+    }
+}
+
+/// Take elements from the iterator up to N, checking each element against the
+/// already collected prefix before storing it.
+///
+/// `check` returns whether to store the element (`Ok(true)`) or drop it and move
+/// on to the next one (`Ok(false)`). Stops at the first error returned by `check`,
+/// dropping the collected prefix along with the rejected element.
+fn collect_validated<I, F, const N: usize>(iter: &mut I, mut check: F) -> Result<[I::Item; N], ToArrayError>
+    where I: Iterator, F: FnMut(&[I::Item], &I::Item) -> Result<bool, ToArrayError> {
+    let mut res = ArrayBuilder::new();
+
+    while !res.is_full() {
+        let x = iter.next().ok_or(ToArrayError::TooShort(res.len, N))?;
+        if check(res.as_slice(), &x)? {
+            res.put(x);
+        }
+    }
+
+    Ok(res.into_array())
+}
+
+pub trait ToArrayPartialOrd<T> {
+    /// Collect the iterator to an array of size N, checking that it is sorted in non-decreasing order.
+    ///
+    /// Collection stops at the first element that is less than (or not comparable to) its
+    /// predecessor, returning Err(ToArrayError::NotSorted) with its index.
+    fn to_array_if_sorted<const N: usize>(self) -> Result<[T; N], ToArrayError>;
+
+    /// Collect the iterator to an array of size N, along with the index of its maximum.
+    ///
+    /// Ties resolve to the first occurrence. N must be greater than 0.
+    fn to_array_argmax<const N: usize>(self) -> Result<([T; N], usize), ToArrayError>;
+
+    /// Collect the iterator to an array of size N, along with the index of its minimum.
+    ///
+    /// Ties resolve to the first occurrence. N must be greater than 0.
+    fn to_array_argmin<const N: usize>(self) -> Result<([T; N], usize), ToArrayError>;
+
+    /// Collect the iterator to an array of size N, along with a mask of its local maxima.
+    ///
+    /// `peaks[i]` is true if `0 < i < N - 1` and the element at `i` is greater than both neighbours.
+    fn to_array_peaks<const N: usize>(self) -> Result<([T; N], [bool; N]), ToArrayError>;
+
+    /// Collect the iterator to an array of size N, checking that the elements alternate in sign.
+    ///
+    /// Signs are taken relative to `T::default()`, and every element must be strictly
+    /// positive or strictly negative. Collection stops at the first element that has no
+    /// sign or the same sign as its predecessor, returning Err(ToArrayError::NotAlternating)
+    /// with its index.
+    fn to_array_alternating<const N: usize>(self) -> Result<[T; N], ToArrayError> where T: Default;
+
+    /// Collect the iterator to an array of size N, along with its minimum and maximum,
+    /// tracked while collecting.
+    ///
+    /// Ties resolve to the first occurrence, and elements not comparable to the current
+    /// minimum or maximum never replace it. N must be greater than 0.
+    fn to_array_minmax<const N: usize>(self) -> Result<([T; N], T, T), ToArrayError> where T: Copy;
+}
+
+/// Index of the first maximum (for `Ordering::Greater`) or minimum (for `Ordering::Less`) of `arr`.
+fn arg_best<T: PartialOrd, const N: usize>(arr: &[T; N], better: Ordering) -> usize {
+    const { assert!(N > 0, "cannot find the extremum of an empty array") };
+    let mut best = 0;
+    for (i, x) in arr.iter().enumerate().skip(1) {
+        if x.partial_cmp(&arr[best]) == Some(better) {
+            best = i;
+        }
+    }
+    best
+}
+
+impl<I, T: PartialOrd> ToArrayPartialOrd<T> for I where I: Iterator<Item=T> {
+    fn to_array_if_sorted<const N: usize>(mut self) -> Result<[T; N], ToArrayError> {
+        let arr = collect_validated(&mut self, |prefix, x| match prefix.last().map(|prev| prev.partial_cmp(x)) {
+            Some(Some(Ordering::Greater)) | Some(None) => Err(ToArrayError::NotSorted(prefix.len())),
+            _ => Ok(true)
+        })?;
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(N)),
+            None => Ok(arr)
+        }
+    }
+
+    fn to_array_argmax<const N: usize>(self) -> Result<([T; N], usize), ToArrayError> {
+        let arr = self.to_array()?;
+        let i = arg_best(&arr, Ordering::Greater);
+        Ok((arr, i))
+    }
+
+    fn to_array_argmin<const N: usize>(self) -> Result<([T; N], usize), ToArrayError> {
+        let arr = self.to_array()?;
+        let i = arg_best(&arr, Ordering::Less);
+        Ok((arr, i))
+    }
+
+    fn to_array_peaks<const N: usize>(self) -> Result<([T; N], [bool; N]), ToArrayError> {
+        let arr: [T; N] = self.to_array()?;
+        let peaks = core::array::from_fn(|i| i > 0 && i + 1 < N && arr[i] > arr[i - 1] && arr[i] > arr[i + 1]);
+        Ok((arr, peaks))
+    }
+
+    fn to_array_alternating<const N: usize>(mut self) -> Result<[T; N], ToArrayError> where T: Default {
+        let zero = T::default();
+        let arr = collect_validated(&mut self, |prefix, x| {
+            let sign = x.partial_cmp(&zero);
+            let prev_sign = prefix.last().and_then(|prev| prev.partial_cmp(&zero));
+            match sign {
+                Some(Ordering::Greater) | Some(Ordering::Less) if sign != prev_sign => Ok(true),
+                _ => Err(ToArrayError::NotAlternating(prefix.len()))
+            }
+        })?;
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(N)),
+            None => Ok(arr)
+        }
+    }
+
+    fn to_array_minmax<const N: usize>(self) -> Result<([T; N], T, T), ToArrayError> where T: Copy {
+        const { assert!(N > 0, "cannot find the extremum of an empty array") };
+        let mut minmax: Option<(T, T)> = None;
+        let arr = self.inspect(|&x| match &mut minmax {
+            Some((min, max)) => {
+                if x < *min {
+                    *min = x;
+                }
+                if x > *max {
+                    *max = x;
+                }
+            },
+            None => minmax = Some((x, x))
+        }).to_array()?;
+        let (min, max) = minmax.expect("a non-empty array was collected");
+        Ok((arr, min, max))
+    }
+}
+
+pub trait ToArrayExt<T> {
+    /// Collect anything that implements `IntoIterator` to an array of size N.
+    ///
+    /// Shorthand for `self.into_iter().to_array()`, with the same errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_to_array::*;
+    /// assert_eq!(vec![1, 2, 3].into_array(), Ok([1, 2, 3]));
+    /// ```
+    fn into_array<const N: usize>(self) -> Result<[T; N], ToArrayError>;
+}
+
+impl<I, T> ToArrayExt<T> for I where I: IntoIterator<Item=T> {
+    fn into_array<const N: usize>(self) -> Result<[T; N], ToArrayError> {
+        self.into_iter().to_array()
+    }
+}
+
+/// Collect `iter` to an array of size N.
+///
+/// Accepts anything that implements `IntoIterator`, and behaves exactly like
+/// `iter.into_iter().to_array()`.
+pub fn collect_array<I: IntoIterator, const N: usize>(iter: I) -> Result<[I::Item; N], ToArrayError> {
+    iter.into_iter().to_array()
+}
+
+/// Collect an iterator to an array with `collect_array`, optionally giving the size after a `;`.
+///
+/// ```
+/// use iter_to_array::*;
+/// assert_eq!(collect_array!(0..5; 5), Ok([0,1,2,3,4]));
+/// let arr: Result<[i32; 2], _> = collect_array!(vec![1, 2]);
+/// assert_eq!(arr, Ok([1, 2]));
+/// ```
+#[macro_export]
+macro_rules! collect_array {
+    ($iter:expr; $n:expr) => {
+        $crate::collect_array::<_, { $n }>($iter)
+    };
+    ($iter:expr) => {
+        $crate::collect_array($iter)
+    };
+}
+
+/// Collect `iter` to an array with the same length as `template`.
+///
+/// Only the length of `template` is used, so N does not need to be spelled out.
+pub fn map_array_from_iter<A, I: Iterator, const N: usize>(_template: &[A; N], iter: I) -> Result<[I::Item; N], ToArrayError> {
+    iter.to_array()
+}
+
+/// Collect pairs from `a` and `b`, advanced in lockstep, to an array of size N.
+///
+/// If either runs out before N pairs are formed, returns Err(ToArrayError::TooShort)
+/// with the number of complete pairs. An element taken from `a` without a partner
+/// from `b` is dropped. If both have elements left after N pairs, returns
+/// Err(ToArrayError::TooLong).
+pub fn zip_to_array<A, B, IA, IB, const N: usize>(a: IA, b: IB) -> Result<[(A, B); N], ToArrayError>
+    where IA: IntoIterator<Item=A>, IB: IntoIterator<Item=B> {
+    a.into_iter().zip(b).to_array()
+}
+
+/// Clone the values of `map` in ascending key order into an array of size N.
+///
+/// If the map has fewer than N entries, returns Err(ToArrayError::TooShort).
+/// If the map has more than N entries, returns Err(ToArrayError::TooLong).
+#[cfg(feature = "std")]
+pub fn to_array_by_keys<K: Ord, T: Clone, const N: usize>(map: &std::collections::BTreeMap<K, T>) -> Result<[T; N], ToArrayError> {
+    map.values().cloned().to_array()
+}
+
+/// Collect the stream to an array of size N.
+///
+/// If the stream ends before N items, returns Err(ToArrayError::TooShort).
+/// If the stream yields more than N items, returns Err(ToArrayError::TooLong).
+/// Items already received are dropped if the future fails or is dropped early.
+#[cfg(feature = "futures")]
+pub async fn stream_to_array<S: futures::Stream<Item=T>, T, const N: usize>(stream: S) -> Result<[T; N], ToArrayError> {
+    use futures::StreamExt;
+    let mut stream = core::pin::pin!(stream);
+    let mut res: [Option<T>; N] = core::array::from_fn(|_| None);
+    for (i, el) in res.iter_mut().enumerate() {
+        match stream.next().await {
+            Some(x) => *el = Some(x),
+            None => return Err(ToArrayError::TooShort(i, N))
+        }
+    }
+    match stream.next().await {
+        Some(_) => Err(ToArrayError::TooLong(N)),
+        None => Ok(res.map(|x| x.expect("all slots are filled")))
+    }
+}
+
+pub trait ToArrayPeekable<T> {
+    /// Take up to N groups of adjacent elements, merging each group into one array element.
+    ///
+    /// Consecutive elements for which `same(current, next)` returns true are combined
+    /// with `merge`. Because the iterator is peekable, the element that ends the last
+    /// group is left in the iterator.
+    /// If there are fewer than N groups, returns Err(ToArrayError::TooShort).
+    fn take_array_merge_adjacent<S, M, const N: usize>(&mut self, same: S, merge: M) -> Result<[T; N], ToArrayError>
+        where S: FnMut(&T, &T) -> bool, M: FnMut(T, T) -> T;
+}
+
+impl<I, T> ToArrayPeekable<T> for Peekable<I> where I: Iterator<Item=T> {
+    fn take_array_merge_adjacent<S, M, const N: usize>(&mut self, mut same: S, mut merge: M) -> Result<[T; N], ToArrayError>
+        where S: FnMut(&T, &T) -> bool, M: FnMut(T, T) -> T {
+        core::iter::from_fn(|| {
+            let mut current = self.next()?;
+            while let Some(x) = self.next_if(|x| same(&current, x)) {
+                current = merge(current, x);
+            }
+            Some(current)
+        }).take_array()
+    }
+}
+
+pub trait ToArrayPeek<T> {
+    /// Look at the next N elements as an array without advancing the iterator.
+    ///
+    /// Works on a clone of the iterator, so the elements are produced twice: once here
+    /// and again when the original iterator reaches them. If there are fewer than N
+    /// elements, returns Err(ToArrayError::TooShort) and drops the peeked elements.
+    fn peek_array<const N: usize>(&self) -> Result<[T; N], ToArrayError>;
+}
+
+impl<I, T: Clone> ToArrayPeek<T> for I where I: Iterator<Item=T> + Clone {
+    fn peek_array<const N: usize>(&self) -> Result<[T; N], ToArrayError> {
+        self.clone().take_array()
+    }
+}
+
+pub trait ToArrayExactSize<T> {
+    /// Like `to_array`, but if `len` differs from N the error is returned without
+    /// consuming any elements.
+    ///
+    /// This relies on the `ExactSizeIterator` contract that `len` is exact. If `len`
+    /// matches N, the length is still checked while collecting.
+    fn to_array_exact<const N: usize>(self) -> Result<[T; N], ToArrayError>;
+}
+
+impl<I, T> ToArrayExactSize<T> for I where I: ExactSizeIterator<Item=T> {
+    fn to_array_exact<const N: usize>(self) -> Result<[T; N], ToArrayError> {
+        match self.len() {
+            len if len < N => Err(ToArrayError::TooShort(len, N)),
+            len if len > N => Err(ToArrayError::TooLong(N)),
+            _ => self.to_array()
+        }
+    }
+}
+
+pub trait ToArrayBack<T> {
+    /// Take the last N elements from the back of the iterator into an array,
+    /// keeping them in forward order.
+    ///
+    /// The last element of the iterator ends up at index N - 1. If the iterator
+    /// is too short, returns Err(ToArrayError::TooShort) and drops the taken elements.
+    fn take_array_back<const N: usize>(&mut self) -> Result<[T; N], ToArrayError>;
+}
+
+impl<I, T> ToArrayBack<T> for I where I: DoubleEndedIterator<Item=T> {
+    fn take_array_back<const N: usize>(&mut self) -> Result<[T; N], ToArrayError> {
+        let mut arr: [T; N] = self.by_ref().rev().take_array()?;
+        arr.reverse();
+        Ok(arr)
+    }
+}
+
+pub trait ToArrayEq<T> {
+    /// Collect the iterator to an array of size N, checking that all elements are distinct.
+    ///
+    /// Collection stops at the first element equal to an earlier one, returning
+    /// Err(ToArrayError::Duplicate) with its index.
+    fn to_array_no_dups<const N: usize>(self) -> Result<[T; N], ToArrayError>;
+
+    /// Take elements from the iterator up to N, skipping any element equal to one of
+    /// the last `window` elements taken.
+    ///
+    /// If the iterator runs out before N elements are taken, returns Err(ToArrayError::TooShort).
+    fn take_array_dedup_window<const N: usize>(&mut self, window: usize) -> Result<[T; N], ToArrayError>;
+
+    /// Take a frame of N elements whose last element is a checksum of the others.
+    ///
+    /// If `checksum` of the first N - 1 elements differs from the last element,
+    /// returns Err(ToArrayError::BadChecksum). N must be greater than 0.
+    /// If the iterator is too short, returns Err(ToArrayError::TooShort).
+    fn take_frame_checked<F: Fn(&[T]) -> T, const N: usize>(&mut self, checksum: F) -> Result<[T; N], ToArrayError>;
+
+    /// Collect the iterator to an array of size N, along with the length of its
+    /// longest run of consecutive equal elements.
+    ///
+    /// The run length is 0 for N = 0.
+    fn to_array_max_run<const N: usize>(self) -> Result<([T; N], usize), ToArrayError>;
+
+    /// Collect the iterator to an array of size N, along with its run-length encoding.
+    ///
+    /// The encoding lists each run of consecutive equal elements as `(value, count)`.
+    #[cfg(feature = "alloc")]
+    fn to_array_rle<const N: usize>(self) -> Result<([T; N], Runs<T>), ToArrayError> where T: Clone;
+
+    /// Collect the iterator to an array of size N, along with the indices `(first, second)`
+    /// of the earliest repeat, i.e. the first element equal to an earlier one.
+    ///
+    /// Returns None as the second value if all elements are distinct.
+    fn to_array_first_dup<const N: usize>(self) -> Result<([T; N], Option<IndexPair>), ToArrayError>;
+}
+
+impl<I, T: PartialEq> ToArrayEq<T> for I where I: Iterator<Item=T> {
+    fn to_array_no_dups<const N: usize>(mut self) -> Result<[T; N], ToArrayError> {
+        let arr = collect_validated(&mut self, |prefix, x| if prefix.contains(x) {
+            Err(ToArrayError::Duplicate(prefix.len()))
+        } else {
+            Ok(true)
+        })?;
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(N)),
+            None => Ok(arr)
+        }
+    }
+
+    fn take_array_dedup_window<const N: usize>(&mut self, window: usize) -> Result<[T; N], ToArrayError> {
+        collect_validated(self, |prefix, x| {
+            let recent = &prefix[prefix.len().saturating_sub(window)..];
+            Ok(!recent.contains(x))
+        })
+    }
+
+    fn take_frame_checked<F: Fn(&[T]) -> T, const N: usize>(&mut self, checksum: F) -> Result<[T; N], ToArrayError> {
+        const { assert!(N > 0, "a checked frame needs room for the checksum") };
+        let arr: [T; N] = self.take_array()?;
+        if checksum(&arr[..N - 1]) == arr[N - 1] {
+            Ok(arr)
+        } else {
+            Err(ToArrayError::BadChecksum)
+        }
+    }
+
+    fn to_array_max_run<const N: usize>(mut self) -> Result<([T; N], usize), ToArrayError> {
+        let mut run = 0;
+        let mut max_run = 0;
+        let arr = collect_validated(&mut self, |prefix, x| {
+            run = if prefix.last() == Some(x) { run + 1 } else { 1 };
+            max_run = max_run.max(run);
+            Ok(true)
+        })?;
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(N)),
+            None => Ok((arr, max_run))
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn to_array_rle<const N: usize>(self) -> Result<([T; N], Runs<T>), ToArrayError> where T: Clone {
+        let arr: [T; N] = self.to_array()?;
+        let mut rle = Runs::new();
+        for x in &arr {
+            match rle.last_mut() {
+                Some((value, count)) if value == x => *count += 1,
+                _ => rle.push((x.clone(), 1))
+            }
+        }
+        Ok((arr, rle))
+    }
+
+    fn to_array_first_dup<const N: usize>(mut self) -> Result<([T; N], Option<IndexPair>), ToArrayError> {
+        let mut first_dup = None;
+        let arr = collect_validated(&mut self, |prefix, x| {
+            if first_dup.is_none() {
+                first_dup = prefix.iter().position(|el| el == x).map(|i| (i, prefix.len()));
+            }
+            Ok(true)
+        })?;
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(N)),
+            None => Ok((arr, first_dup))
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub trait ToArrayHash<T> {
+    /// Collect the iterator to an array of size N, along with the Shannon entropy
+    /// (in bits) of the distribution of its values.
+    ///
+    /// The entropy is 0.0 for N = 0.
+    fn to_array_entropy<const N: usize>(self) -> Result<([T; N], f64), ToArrayError>;
+}
+
+#[cfg(feature = "std")]
+impl<I, T: Eq + core::hash::Hash> ToArrayHash<T> for I where I: Iterator<Item=T> {
+    fn to_array_entropy<const N: usize>(self) -> Result<([T; N], f64), ToArrayError> {
+        let arr: [T; N] = self.to_array()?;
+        let mut counts = std::collections::HashMap::new();
+        for x in &arr {
+            *counts.entry(x).or_insert(0usize) += 1;
+        }
+        let entropy = counts.values().fold(0.0, |sum, &count| {
+            let p = count as f64 / N as f64;
+            sum + p * (1.0 / p).log2()
+        });
+        Ok((arr, entropy))
+    }
+}
+
+/// Numbers that can be converted to `f64` for the `ToArrayStats` helpers.
+///
+/// Unlike `Into<f64>`, this covers every primitive integer. Integers wider than
+/// 52 bits are rounded to the nearest `f64`.
+pub trait AsF64: Copy {
+    fn as_f64(self) -> f64;
+}
+
+macro_rules! impl_as_f64 {
+    ($($t:ty)*) => {
+        $(
+            impl AsF64 for $t {
+                fn as_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    }
+}
+
+impl_as_f64!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64);
+
+pub trait ToArrayStats<T> {
+    /// Collect the iterator to an array of size N, along with the arithmetic mean of its elements.
+    ///
+    /// The mean is computed in `f64`, and is NaN for N = 0.
+    fn to_array_mean<const N: usize>(self) -> Result<([T; N], f64), ToArrayError>;
+
+    /// Collect the iterator to an array of size N, along with the mean and population variance of its elements.
+    ///
+    /// Both are computed in `f64` in a single pass using Welford's algorithm, and are NaN for N = 0.
+    fn to_array_stats<const N: usize>(self) -> Result<([T; N], f64, f64), ToArrayError>;
+
+    /// Collect the iterator to an array of size N with exponential smoothing applied.
+    ///
+    /// `out[0] = x[0]` and `out[i] = alpha * x[i] + (1 - alpha) * out[i - 1]`.
+    fn to_array_smoothed<const N: usize>(self, alpha: f64) -> Result<[f64; N], ToArrayError>;
+}
+
+impl<I, T: AsF64> ToArrayStats<T> for I where I: Iterator<Item=T> {
+    fn to_array_mean<const N: usize>(self) -> Result<([T; N], f64), ToArrayError> {
+        let arr: [T; N] = self.to_array()?;
+        let sum: f64 = arr.iter().map(|&x| x.as_f64()).sum();
+        Ok((arr, sum / N as f64))
+    }
+
+    fn to_array_stats<const N: usize>(self) -> Result<([T; N], f64, f64), ToArrayError> {
+        let arr: [T; N] = self.to_array()?;
+        if N == 0 {
+            return Ok((arr, f64::NAN, f64::NAN));
+        }
+        let mut mean = 0.0;
+        let mut m2 = 0.0;
+        for (i, &x) in arr.iter().enumerate() {
+            let x = x.as_f64();
+            let delta = x - mean;
+            mean += delta / (i + 1) as f64;
+            m2 += delta * (x - mean);
+        }
+        Ok((arr, mean, m2 / N as f64))
+    }
+
+    fn to_array_smoothed<const N: usize>(self, alpha: f64) -> Result<[f64; N], ToArrayError> {
+        let mut arr: [f64; N] = self.map(AsF64::as_f64).to_array()?;
+        for i in 1..N {
+            arr[i] = alpha * arr[i] + (1.0 - alpha) * arr[i - 1];
+        }
+        Ok(arr)
+    }
+}
+
+pub trait TryToArray<T, E> {
+    /// Take `Ok` values from the iterator up to N, stopping at the first `Err`.
+    ///
+    /// Returns Ok(Err(e)) for the first error `e`, dropping the values taken so far.
+    /// The error element is consumed, and the rest of the iterator is left untouched.
+    /// If the iterator is too short, returns Err(ToArrayError::TooShort).
+    fn try_take_array<const N: usize>(&mut self) -> Result<Result<[T; N], E>, ToArrayError>;
+
+    /// Collect the `Ok` values of the iterator to an array of size N, stopping at the first `Err`.
+    ///
+    /// Returns Ok(Err(e)) for the first error `e`, dropping the values collected so far.
+    fn try_to_array<const N: usize>(self) -> Result<Result<[T; N], E>, ToArrayError>;
+
+    /// Like `try_to_array`, but on an `Err` element the collection is retried up to
+    /// `retries` times with a fresh iterator from `make_iter`.
+    ///
+    /// Returns Ok(Err(e)) with the error of the last attempt if every attempt hit an `Err`
+    /// element. A length error is never retried: it ends collection at once, even if retries
+    /// remain, and is returned as the outer Err(ToArrayError).
+    fn try_to_array_retry<F: FnMut() -> Self, const N: usize>(self, retries: usize, make_iter: F) -> Result<Result<[T; N], E>, ToArrayError>;
+}
+
+impl<I, T, E> TryToArray<T, E> for I where I: Iterator<Item=Result<T, E>> {
+    fn try_take_array<const N: usize>(&mut self) -> Result<Result<[T; N], E>, ToArrayError> {
+        let mut error = None;
+        let arr = self.map_while(|x| match x {
+            Ok(x) => Some(x),
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        }).take_array();
+        match error {
+            Some(e) => Ok(Err(e)),
+            None => arr.map(Ok)
+        }
+    }
+
+    fn try_to_array<const N: usize>(mut self) -> Result<Result<[T; N], E>, ToArrayError> {
+        let arr = match self.try_take_array()? {
+            Ok(arr) => arr,
+            Err(e) => return Ok(Err(e))
+        };
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(N)),
+            None => Ok(Ok(arr))
+        }
+    }
+
+    fn try_to_array_retry<F: FnMut() -> Self, const N: usize>(self, retries: usize, mut make_iter: F) -> Result<Result<[T; N], E>, ToArrayError> {
+        let mut result = self.try_to_array();
+        for _ in 0..retries {
+            match result {
+                Ok(Err(_)) => result = make_iter().try_to_array(),
+                _ => break
+            }
+        }
+        result
+    }
+}
+
+pub trait ToArraySparse<U> {
+    /// Take elements from the iterator up to N, then apply `(index, value)` updates.
+    ///
+    /// Slots the iterator does not reach are filled with `default()`, so an empty
+    /// iterator starts from an array of defaults. Each update overwrites the slot at its
+    /// index. Later updates to the same index win, and updates with an index of N or
+    /// greater are ignored.
+    fn take_array_sparse<D: Fn() -> U, J: Iterator<Item=(usize, U)>, const N: usize>(&mut self, default: D, updates: J) -> [U; N];
+}
+
+impl<I, U> ToArraySparse<U> for I where I: Iterator<Item=U> {
+    fn take_array_sparse<D: Fn() -> U, J: Iterator<Item=(usize, U)>, const N: usize>(&mut self, default: D, updates: J) -> [U; N] {
+        let mut arr: [U; N] = match self.take_array_partial(&default) {
+            MaybePartial::Full(arr) | MaybePartial::Partial(arr, _) => arr,
+            MaybePartial::Empty => core::array::from_fn(|_| default())
+        };
+        for (i, x) in updates {
+            if let Some(slot) = arr.get_mut(i) {
+                *slot = x;
+            }
+        }
+        arr
+    }
+}
+
+pub trait CharIterExt {
+    /// Take N chars from the iterator, checking each against `allowed`.
+    ///
+    /// Collection stops at the first char rejected by `allowed`, returning
+    /// Err(ToArrayError::InvalidChar) with its index and value.
+    /// If the iterator is too short, returns Err(ToArrayError::TooShort).
+    fn take_char_array_matching<A: Fn(char) -> bool, const N: usize>(&mut self, allowed: A) -> Result<[char; N], ToArrayError>;
+}
+
+impl<I> CharIterExt for I where I: Iterator<Item=char> {
+    fn take_char_array_matching<A: Fn(char) -> bool, const N: usize>(&mut self, allowed: A) -> Result<[char; N], ToArrayError> {
+        collect_validated(self, |prefix, &c| if allowed(c) {
+            Ok(true)
+        } else {
+            Err(ToArrayError::InvalidChar(prefix.len(), c))
+        })
+    }
+}
+
+pub trait FloatIterExt {
+    /// Collect the iterator to an array of size N, sorted by `f64::total_cmp`.
+    ///
+    /// Unlike `partial_cmp` this is a total order: -0.0 sorts before 0.0, and NaNs
+    /// sort after +inf (or before -inf if their sign bit is set).
+    fn to_sorted_array_total<const N: usize>(self) -> Result<[f64; N], ToArrayError>;
+}
+
+impl<I> FloatIterExt for I where I: Iterator<Item=f64> {
+    fn to_sorted_array_total<const N: usize>(self) -> Result<[f64; N], ToArrayError> {
+        let mut arr: [f64; N] = self.to_array()?;
+        arr.sort_unstable_by(f64::total_cmp);
+        Ok(arr)
+    }
+}
+
+pub trait ToArrayDelta<T> {
+    /// Collect the iterator to an array of size N, along with its delta encoding.
+    ///
+    /// The first delta is the first element, and each following delta is the
+    /// difference from the previous element.
+    fn to_array_delta<const N: usize>(self) -> Result<([T; N], [T; N]), ToArrayError>;
+}
+
+impl<I, T: Sub<Output=T> + Copy> ToArrayDelta<T> for I where I: Iterator<Item=T> {
+    fn to_array_delta<const N: usize>(self) -> Result<([T; N], [T; N]), ToArrayError> {
+        let arr: [T; N] = self.to_array()?;
+        let delta = core::array::from_fn(|i| if i == 0 { arr[0] } else { arr[i] - arr[i - 1] });
+        Ok((arr, delta))
+    }
+}
+
+/// A fixed array of key-value pairs, searchable by key.
+#[derive(Clone,Debug,PartialEq)]
+pub struct LookupArray<K, V, const N: usize> {
+    pub entries: [(K, V); N],
+}
+
+impl<K: PartialEq, V, const N: usize> LookupArray<K, V, N> {
+    /// Find the value of the first entry with the given key, using a linear search.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+pub trait ToKeyValueArray<K, V> {
+    /// Collect the key-value pairs of the iterator to a `LookupArray` of size N.
+    fn to_array_with_lookup<const N: usize>(self) -> Result<LookupArray<K, V, N>, ToArrayError>;
+
+    /// Collect the key-value pairs of the iterator to an array of size N, checking
+    /// that the keys are strictly ascending.
+    ///
+    /// Collection stops at the first key that is not greater than the previous one,
+    /// returning Err(ToArrayError::NotSorted) with its index.
+    fn to_sorted_kv_array<const N: usize>(self) -> Result<[(K, V); N], ToArrayError> where K: Ord;
+}
+
+impl<I, K: PartialEq, V> ToKeyValueArray<K, V> for I where I: Iterator<Item=(K, V)> {
+    fn to_array_with_lookup<const N: usize>(self) -> Result<LookupArray<K, V, N>, ToArrayError> {
+        Ok(LookupArray { entries: self.to_array()? })
+    }
+
+    fn to_sorted_kv_array<const N: usize>(mut self) -> Result<[(K, V); N], ToArrayError> where K: Ord {
+        let arr = collect_validated(&mut self, |prefix, (k, _)| match prefix.last() {
+            Some((prev, _)) if prev >= k => Err(ToArrayError::NotSorted(prefix.len())),
+            _ => Ok(true)
+        })?;
+        match self.next() {
+            Some(_) => Err(ToArrayError::TooLong(N)),
+            None => Ok(arr)
+        }
+    }
+}
+
+/// Integer types with overflow-checked multiplication.
+pub trait CheckedMul: Sized {
+    const ONE: Self;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_mul {
+    ($($t:ty)*) => {
+        $(
+            impl CheckedMul for $t {
+                const ONE: Self = 1;
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+            }
+        )*
+    }
+}
+
+impl_checked_mul!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+pub trait ToArrayProduct<T> {
+    /// Collect the iterator to an array of size N, along with the product of its elements.
+    ///
+    /// The product is `None` if it overflows `T`.
+    fn to_array_checked_product<const N: usize>(self) -> Result<([T; N], Option<T>), ToArrayError>;
+}
+
+impl<I, T: CheckedMul + Copy> ToArrayProduct<T> for I where I: Iterator<Item=T> {
+    fn to_array_checked_product<const N: usize>(self) -> Result<([T; N], Option<T>), ToArrayError> {
+        let arr: [T; N] = self.to_array()?;
+        let product = arr.iter().try_fold(T::ONE, |acc, &x| acc.checked_mul(x));
+        Ok((arr, product))
+    }
+}
+
+#[derive(Clone)]
+pub struct ChunksIter<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> {
+    iter: I,
+    padding: F,
+}
+
+impl<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> Iterator for ChunksIter<I, F, N> 
+    where I: Iterator {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<[I::Item; N]> {
+        let ChunksIter { iter, padding } = self;
+        match iter.take_array_partial(padding) {
+            MaybePartial::Empty => None,
+            MaybePartial::Partial(x, _) => Some(x),
+            MaybePartial::Full(x) => Some(x)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        (lower.div_ceil(N), upper.map(|upper| upper.div_ceil(N)))
+    }
+}
+
+impl<I: FusedIterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> FusedIterator for ChunksIter<I, F, N> {}
+
+impl<I: ExactSizeIterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> ExactSizeIterator for ChunksIter<I, F, N> {}
+
+impl<I, F: FnMut() -> <I as Iterator>::Item, const N: usize> DoubleEndedIterator for ChunksIter<I, F, N>
+    where I: DoubleEndedIterator + ExactSizeIterator {
+    /// Yield the last chunk, which is the padded one if the length is not a multiple of N.
+    fn next_back(&mut self) -> Option<[I::Item; N]> {
+        let len = self.iter.len();
+        if len == 0 {
+            return None;
+        }
+        let taken = match len % N {
+            0 => N,
+            rem => rem
+        };
+        let ChunksIter { iter, padding } = self;
+        let mut chunk = match iter.rev().take(taken).take_array_partial(padding) {
+            MaybePartial::Full(x) | MaybePartial::Partial(x, _) => x,
+            MaybePartial::Empty => return None
+        };
+        chunk[..taken].reverse();
+        Some(chunk)
+    }
+}
+
+/// Chunks iterator that yields each chunk as a `MaybePartial`.
+///
+/// Full chunks are yielded as `Full`, and an incomplete final chunk as `Partial`
+/// with its tail filled by the padding closure. `Empty` is never yielded.
+pub struct PartialChunksIter<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> {
+    iter: I,
+    padding: F,
+}
+
+impl<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> Iterator for PartialChunksIter<I, F, N> {
+    type Item = MaybePartial<[I::Item; N]>;
+
+    fn next(&mut self) -> Option<MaybePartial<[I::Item; N]>> {
+        let PartialChunksIter { iter, padding } = self;
+        match iter.take_array_partial(padding) {
+            MaybePartial::Empty => None,
+            chunk => Some(chunk)
+        }
+    }
+}
+
+/// Chunks iterator whose padding closure is passed the index within the chunk of the slot it fills.
+pub struct IndexedChunksIter<I: Iterator, F: FnMut(usize) -> <I as Iterator>::Item, const N: usize> {
+    iter: I,
+    padding: F,
+}
+
+impl<I: Iterator, F: FnMut(usize) -> <I as Iterator>::Item, const N: usize> Iterator for IndexedChunksIter<I, F, N> {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<[I::Item; N]> {
+        let IndexedChunksIter { iter, padding } = self;
+        match iter.take_array_partial_indexed(padding) {
+            MaybePartial::Empty => None,
+            MaybePartial::Partial(x, _) => Some(x),
+            MaybePartial::Full(x) => Some(x)
+        }
+    }
+}
+
+/// Iterator over overlapping windows of N elements, advancing one element at a time.
+///
+/// Each window is cloned out of an internal ring buffer holding the last N elements.
+/// Nothing is yielded once the source has returned None.
+pub struct WindowsIter<I: Iterator, const N: usize> {
+    iter: I,
+    window: Option<[I::Item; N]>,
+    /// Index of the oldest element in `window`.
+    head: usize,
+    done: bool,
+}
+
+impl<I: Iterator, const N: usize> Iterator for WindowsIter<I, N> where I::Item: Clone {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<[I::Item; N]> {
+        if self.done {
+            return None;
+        }
+        let WindowsIter { iter, window, head, done } = self;
+        match window {
+            Some(window) => match iter.next() {
+                Some(x) => {
+                    window[*head] = x;
+                    *head = (*head + 1) % N;
+                },
+                None => *done = true
+            },
+            None => match iter.take_array() {
+                Ok(first) => *window = Some(first),
+                Err(_) => *done = true
+            }
+        }
+        if *done {
+            return None;
+        }
+        let window = window.as_ref()?;
+        Some(core::array::from_fn(|i| window[(*head + i) % N].clone()))
+    }
+}
+
+/// Chunks iterator that takes chunks from the back of the iterator.
+///
+/// Elements within each chunk keep their original order. The final chunk holds the
+/// front of the iterator, and is padded at its start if it is incomplete.
+pub struct RChunksIter<I: DoubleEndedIterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> {
+    iter: I,
+    padding: F,
+}
+
+impl<I: DoubleEndedIterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> Iterator for RChunksIter<I, F, N> {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<[I::Item; N]> {
+        let RChunksIter { iter, padding } = self;
+        let mut chunk = match iter.rev().take_array_partial(padding) {
+            MaybePartial::Empty => return None,
+            MaybePartial::Partial(x, _) => x,
+            MaybePartial::Full(x) => x
+        };
+        chunk.reverse();
+        Some(chunk)
+    }
+}
+
+/// Chunks iterator yielding chunks of N elements whose starts are S elements apart.
+///
+/// For S > N the elements between chunks are skipped, and for S < N consecutive chunks
+/// overlap, with the shared elements cloned from the previous chunk. A chunk is yielded
+/// for every start position within the iterator, padding the ones that run past its end.
+pub struct StepChunksIter<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize, const S: usize> {
+    iter: I,
+    padding: F,
+    /// The previous chunk, kept only if chunks overlap.
+    last: Option<[I::Item; N]>,
+    /// The number of real elements in the previous chunk.
+    real: usize,
+    started: bool,
+    done: bool,
+}
+
+impl<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize, const S: usize> Iterator for StepChunksIter<I, F, N, S>
+    where I::Item: Clone {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<[I::Item; N]> {
+        if self.done {
+            return None;
+        }
+        let mut chunk = ArrayBuilder::new();
+        if let Some(last) = self.last.take() {
+            for x in IntoIterator::into_iter(last).take(self.real).skip(S) {
+                chunk.put(x);
+            }
+        } else if self.started {
+            for _ in N..S {
+                if self.iter.next().is_none() {
+                    self.done = true;
+                    return None;
+                }
+            }
+        }
+        // a partial chunk means the iterator has run out
+        if self.real == N {
+            while !chunk.is_full() {
+                match self.iter.next() {
+                    Some(x) => chunk.put(x),
+                    None => break
+                }
+            }
+        }
+
+        let real = chunk.len;
+        if real == 0 {
+            self.done = true;
+            return None;
+        }
+        while !chunk.is_full() {
+            chunk.put((self.padding)());
+        }
+        let chunk = chunk.into_array();
+        self.started = true;
+        self.real = real;
+        if real < N && S >= real {
+            self.done = true;
+        } else if S < N {
+            self.last = Some(chunk.clone());
+        }
+        Some(chunk)
+    }
+}
+
+/// Chunks iterator that marks the final chunk.
+///
+/// Yields `(is_last, chunk)` pairs, buffering one chunk ahead to know when
+/// the current chunk is the last one.
+pub struct MarkedChunksIter<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> {
+    chunks: Peekable<ChunksIter<I, F, N>>,
+}
+
+impl<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> Iterator for MarkedChunksIter<I, F, N> {
+    type Item = (bool, [I::Item; N]);
+
+    fn next(&mut self) -> Option<(bool, [I::Item; N])> {
+        let chunk = self.chunks.next()?;
+        Some((self.chunks.peek().is_none(), chunk))
+    }
+}
+
+/// Chunks iterator yielding up to N elements per `Vec`, without padding.
+#[cfg(feature = "alloc")]
+pub struct VecChunksIter<I: Iterator, const N: usize> {
+    iter: I,
+}
+
+#[cfg(feature = "alloc")]
+impl<I: Iterator, const N: usize> Iterator for VecChunksIter<I, N> {
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Vec<I::Item>> {
+        let mut chunk = Vec::with_capacity(N);
+        chunk.extend(self.iter.by_ref().take(N));
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+}
+
+/// Chunks iterator yielding only full chunks of N elements.
+///
+/// Once the iterator runs out, the elements of the final incomplete chunk are
+/// available through `into_remainder` instead of being padded.
+pub struct FullChunksIter<I: Iterator, const N: usize> {
+    iter: I,
+    remainder: ArrayBuilder<I::Item, N>,
+    done: bool,
+}
+
+impl<I: Iterator, const N: usize> FullChunksIter<I, N> {
+    /// The elements left over after the last full chunk, in iteration order.
+    ///
+    /// This is empty until `next` has returned None.
+    pub fn into_remainder(self) -> impl Iterator<Item=I::Item> {
+        self.remainder.into_partial_iter()
+    }
+}
+
+impl<I: Iterator, const N: usize> Iterator for FullChunksIter<I, N> {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<[I::Item; N]> {
+        if self.done {
+            return None;
+        }
+        match take_guarded(&mut self.iter) {
+            Ok(chunk) => Some(chunk),
+            Err(remainder) => {
+                self.remainder = remainder;
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+/// Chunks iterator yielding full chunks of N elements, or an error for an incomplete final chunk.
+///
+/// The error is `ToArrayError::TooShortAt`, holding the offset where the incomplete chunk
+/// started. Offsets count from where chunking began, plus the base given to `try_chunks_from`.
+/// The elements of the incomplete chunk are dropped, and nothing is yielded after the error.
+pub struct TryChunksIter<I: Iterator, const N: usize> {
+    iter: I,
+    offset: usize,
+    done: bool,
+}
+
+impl<I: Iterator, const N: usize> Iterator for TryChunksIter<I, N> {
+    type Item = Result<[I::Item; N], ToArrayError>;
+
+    fn next(&mut self) -> Option<Result<[I::Item; N], ToArrayError>> {
+        if self.done {
+            return None;
+        }
+        match take_guarded::<_, N>(&mut self.iter) {
+            Ok(chunk) => {
+                self.offset += N;
+                Some(Ok(chunk))
+            },
+            Err(taken) => {
+                self.done = true;
+                if taken.is_empty() {
+                    None
+                } else {
+                    Some(Err(ToArrayError::TooShortAt { got: taken.len, expected: N, offset: self.offset }))
+                }
+            }
+        }
+    }
+}
+
+/// Chunks iterator that also ends a chunk at each separator element.
+///
+/// Yields `Full` chunks of N elements and padded `Partial` chunks for groups cut
+/// short by a separator or by the end of the iterator. Separators are consumed and
+/// not included, and a separator directly after a full chunk only ends that chunk.
+pub struct SplitChunksIter<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> {
+    iter: Peekable<I>,
+    sep: I::Item,
+    padding: F,
+}
+
+impl<I: Iterator, F: FnMut() -> <I as Iterator>::Item, const N: usize> Iterator for SplitChunksIter<I, F, N>
+    where I::Item: PartialEq {
+    type Item = MaybePartial<[I::Item; N]>;
+
+    fn next(&mut self) -> Option<MaybePartial<[I::Item; N]>> {
+        let SplitChunksIter { iter, sep, padding } = self;
+        iter.peek()?;
+        let mut at_sep = false;
+        let res = iter.by_ref().take_while(|x| {
+            at_sep = x == sep;
+            !at_sep
+        }).take_array_partial(&mut *padding);
+        match res {
+            MaybePartial::Full(x) => {
+                iter.next_if(|x| x == sep);
+                Some(MaybePartial::Full(x))
+            },
+            MaybePartial::Empty => Some(MaybePartial::Partial(core::array::from_fn(|_| padding()), 0)),
+            res => Some(res)
+        }
+    }
+}
+
+/// Fail to compile for a chunk size of 0, which would otherwise yield empty chunks forever.
+fn assert_chunk_size<const N: usize>() {
+    const { assert!(N > 0, "chunks must have at least one element") };
+}
+
+/// Adapters yielding compile-time sized chunks of an iterator.
+///
+/// Every chunk adapter requires N > 0, and using one with N = 0 is a compile error:
+///
+/// ```compile_fail
+/// use iter_to_array::*;
+/// let _ = (0..5).chunks::<_, 0>(|| 0);
+/// ```
+pub trait Chunks: Iterator + Sized {
+    fn chunks<F: FnMut() -> <Self as Iterator>::Item, const N: usize>(self, padding: F) -> ChunksIter<Self, F, N> {
+        assert_chunk_size::<N>();
+        ChunksIter { iter: self, padding }
+    }
+
+    /// Like `chunks`, but chunks are taken from the back of the iterator.
+    ///
+    /// See `RChunksIter` for details.
+    fn rchunks<F: FnMut() -> <Self as Iterator>::Item, const N: usize>(self, padding: F) -> RChunksIter<Self, F, N>
+        where Self: DoubleEndedIterator {
+        assert_chunk_size::<N>();
+        RChunksIter { iter: self, padding }
+    }
+
+    /// Like `chunks`, but each chunk is yielded as a `MaybePartial`, so the final chunk
+    /// reports how many of its elements are real.
+    fn chunks_partial<F: FnMut() -> <Self as Iterator>::Item, const N: usize>(self, padding: F) -> PartialChunksIter<Self, F, N> {
+        assert_chunk_size::<N>();
+        PartialChunksIter { iter: self, padding }
+    }
+
+    /// Like `chunks`, but `padding` is passed the index within the chunk of the slot it fills.
+    fn chunks_indexed<F: FnMut(usize) -> <Self as Iterator>::Item, const N: usize>(self, padding: F) -> IndexedChunksIter<Self, F, N> {
+        assert_chunk_size::<N>();
+        IndexedChunksIter { iter: self, padding }
+    }
+
+    /// Chunks of N elements starting every S elements, padding the ones that run past the end.
+    ///
+    /// See `StepChunksIter` for details. S must be greater than 0.
+    fn step_chunks<F: FnMut() -> <Self as Iterator>::Item, const N: usize, const S: usize>(self, padding: F) -> StepChunksIter<Self, F, N, S>
+        where Self::Item: Clone {
+        assert_chunk_size::<N>();
+        const { assert!(S > 0, "chunks must be at least one element apart") };
+        StepChunksIter { iter: self, padding, last: None, real: N, started: false, done: false }
+    }
+
+    /// Overlapping windows of N elements, advancing one element at a time.
+    ///
+    /// Yields nothing if the iterator has fewer than N elements. N must be greater than 0.
+    fn windows<const N: usize>(self) -> WindowsIter<Self, N> where Self::Item: Clone {
+        const { assert!(N > 0, "windows must have at least one element") };
+        WindowsIter { iter: self, window: None, head: 0, done: false }
+    }
+
+    /// Like `chunks`, but each chunk is paired with a flag that is `true` only for the final chunk.
+    fn chunks_marked<F: FnMut() -> <Self as Iterator>::Item, const N: usize>(self, padding: F) -> MarkedChunksIter<Self, F, N> {
+        MarkedChunksIter { chunks: self.chunks(padding).peekable() }
+    }
+
+    /// Chunks of up to N elements that also end at each `sep` element.
+    ///
+    /// See `SplitChunksIter` for details.
+    fn split_chunks<F: FnMut() -> <Self as Iterator>::Item, const N: usize>(self, sep: Self::Item, padding: F) -> SplitChunksIter<Self, F, N>
+        where Self::Item: PartialEq {
+        assert_chunk_size::<N>();
+        SplitChunksIter { iter: self.peekable(), sep, padding }
+    }
+
+    /// Chunks of exactly N elements, without padding.
+    ///
+    /// See `FullChunksIter` for how to get the leftover elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_to_array::*;
+    /// let mut chunks = (0..9).full_chunks::<4>();
+    /// assert_eq!(chunks.next(), Some([0, 1, 2, 3]));
+    /// assert_eq!(chunks.next(), Some([4, 5, 6, 7]));
+    /// assert_eq!(chunks.next(), None);
+    /// assert!(chunks.into_remainder().eq([8]));
+    /// ```
+    fn full_chunks<const N: usize>(self) -> FullChunksIter<Self, N> {
+        assert_chunk_size::<N>();
+        FullChunksIter { iter: self, remainder: ArrayBuilder::new(), done: false }
+    }
+
+    /// Chunks of exactly N elements, yielding Err(ToArrayError::TooShortAt) instead of
+    /// an incomplete final chunk.
+    fn try_chunks<const N: usize>(self) -> TryChunksIter<Self, N> {
+        self.try_chunks_from(0)
+    }
+
+    /// Like `try_chunks`, but error offsets start at `offset` instead of 0.
+    ///
+    /// Use this when elements were already taken from the source, so that errors
+    /// report positions in the source rather than in the chunked part.
+    fn try_chunks_from<const N: usize>(self, offset: usize) -> TryChunksIter<Self, N> {
+        assert_chunk_size::<N>();
+        TryChunksIter { iter: self, offset, done: false }
+    }
+
+    /// Chunks of up to N elements collected into `Vec`s.
+    ///
+    /// Unlike `chunks`, the final chunk is not padded and may be shorter than N.
+    #[cfg(feature = "alloc")]
+    fn chunks_vec<const N: usize>(self) -> VecChunksIter<Self, N> {
+        assert_chunk_size::<N>();
+        VecChunksIter { iter: self }
+    }
+}
+
+impl<I> Chunks for I where I: Iterator + Sized {}
+
+pub trait ChunksDefault: Iterator + Sized {
+    fn chunks_default<const N: usize>(self) -> ChunksIter<Self, fn() -> <Self as Iterator>::Item, N>;
+}
+
+impl<I> ChunksDefault for I where I: Iterator, <I as Iterator>::Item: Default {
+    fn chunks_default<const N: usize>(self) -> ChunksIter<Self, fn() -> <Self as Iterator>::Item, N> {
+        assert_chunk_size::<N>();
+        ChunksIter { iter: self, padding: Default::default }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_array() {
+        assert_eq!((0..5).to_array(), Ok([0,1,2,3,4]));
+        assert_eq!((0..5).to_array::<10>(), Err(ToArrayError::TooShort(5, 10)));
+        assert_eq!((0..0).to_array::<10>(), Err(ToArrayError::TooShort(0, 10)));
+        assert_eq!((0..5).to_array::<4>(), Err(ToArrayError::TooLong(4)));
+
+        let mut iter = 0..10;
+        assert_eq!(iter.take_array(), Ok([0,1,2,3,4]));
+        assert_eq!(iter.take_array(), Ok([5,6,7]));
+        assert_eq!(iter.take_array::<5>(), Err(ToArrayError::TooShort(2, 5)));
+    }
+
+    #[test]
+    fn to_array_default() {
+        assert_eq!((0..5).to_array_default(), Ok([0,1,2,3,4]));
+        assert_eq!((0..5).to_array_default(), Ok([0,1,2,3,4,0,0]));
+        assert_eq!((0..0).to_array_default(), Ok([0; 10]));
+        assert_eq!((0..5).to_array_default::<4>(), Err(ToArrayError::TooLong(4)));
+
+        let mut iter = 0..8;
         assert_eq!(iter.take_array_default(), [0,1,2,3,4]);
         assert_eq!(iter.take_array_default(), [5,6,7,0,0]);
         assert_eq!(iter.take_array_default(), [0,0,0,0,0]);
     }
 
     #[test]
-    fn to_array_pad() {
-        assert_eq!((0..5).to_array_pad(4), Ok([0,1,2,3,4]));
-        assert_eq!((0..5).to_array_pad(4), Ok([0,1,2,3,4,4,4]));
-        assert_eq!((0..0).to_array_pad(4), Ok([4; 10]));
-        assert_eq!((0..5).to_array_pad::<4>(4), Err(ToArrayError::TooLong(4)));
+    fn to_array_pad() {
+        assert_eq!((0..5).to_array_pad(4), Ok([0,1,2,3,4]));
+        assert_eq!((0..5).to_array_pad(4), Ok([0,1,2,3,4,4,4]));
+        assert_eq!((0..0).to_array_pad(4), Ok([4; 10]));
+        assert_eq!((0..5).to_array_pad::<4>(4), Err(ToArrayError::TooLong(4)));
+
+        let mut iter = 0..8;
+        assert_eq!(iter.take_array_pad(4), [0,1,2,3,4]);
+        assert_eq!(iter.take_array_pad(4), [5,6,7,4,4]);
+        assert_eq!(iter.take_array_pad(4), [4,4,4,4,4]);
+    }
+
+    #[test]
+    fn array_of_vecs() {
+        use std::vec::Vec;
+        let v = vec![(1..5).collect::<Vec<i32>>(); 5];
+        let arr = v.into_iter().to_array::<5>().unwrap();
+        for x in &arr {
+            assert_eq!(*x, vec![1i32,2,3,4])
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn array_of_vecs_fail() {
+        use std::vec::Vec;
+        let v = vec![(1..5).collect::<Vec<i32>>(); 5];
+        v.into_iter().to_array::<6>().unwrap();
+    }
+
+    #[test]
+    fn chunks_iter() {
+        use std::vec::Vec;
+        use std::convert::TryInto;
+        for chunk in (0..5).cycle().take(100).chunks_default() {
+            assert_eq!(chunk, [0,1,2,3,4])
+        }
+        let vec: Vec<usize> = (0..30).collect();
+        let chunks1: Vec<[usize; 6]> = vec.clone().chunks(6).map(|x| x.try_into().unwrap()).collect();
+        let chunks2: Vec<[usize; 6]> = vec.into_iter().chunks_default().collect();
+        assert_eq!(chunks1, chunks2);
+        
+        let vec: Vec<[i32; 4]> = (0..8).chunks(|| -1).collect();
+        assert_eq!(vec,  vec![[0,1,2,3], [4,5,6,7]]);
+
+        let vec: Vec<[i32; 4]> = (0..9).chunks(|| -1).collect();
+        assert_eq!(vec, vec![[0,1,2,3], [4,5,6,7], [8,-1,-1,-1]]);
+
+        let vec: Vec<[i32; 4]> = (0..4).chunks(|| -1).collect();
+        assert_eq!(vec, vec![[0,1,2,3]]);
+
+        let vec: Vec<[i32; 4]> = (0..0).chunks(|| -1).collect();
+        assert_eq!(vec, Vec::<[i32; 4]>::new());
+    }
+
+    #[test]
+    fn to_array_unique_by() {
+        use std::rc::Rc;
+
+        #[derive(Debug, PartialEq)]
+        struct Item {
+            id: u32,
+            name: &'static str,
+            tracker: Rc<()>,
+        }
+
+        let tracker = Rc::new(());
+        let items = |ids: &[(u32, &'static str)]| {
+            ids.iter().map(|&(id, name)| Item { id, name, tracker: tracker.clone() }).collect::<std::vec::Vec<_>>()
+        };
+
+        let arr: [Item; 2] = items(&[(1, "a"), (1, "b"), (2, "c"), (2, "d")]).into_iter().to_array_unique_by(|x| x.id).unwrap();
+        assert_eq!(arr.iter().map(|x| x.name).collect::<std::vec::Vec<_>>(), ["a", "c"]);
+        assert_eq!(Rc::strong_count(&tracker), 3);
+        drop(arr);
+
+        assert_eq!(items(&[(1, "a"), (1, "b"), (1, "c")]).into_iter().to_array_unique_by::<_, _, 2>(|x| x.id), Err(ToArrayError::TooShort(1, 2)));
+        let arr = items(&[(1, "a"), (2, "b"), (3, "c")]).into_iter().to_array_unique_by::<_, _, 2>(|x| x.id).unwrap();
+        assert_eq!(arr.map(|x| x.name), ["a", "b"]);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+
+        // one key call per element, and nothing pulled after the N-th distinct key
+        let mut calls = 0;
+        let mut iter = [1, 1, 2, 3, 4].iter().copied();
+        assert_eq!(iter.by_ref().to_array_unique_by(|&x| { calls += 1; x }), Ok([1, 2]));
+        assert_eq!(calls, 3);
+        assert_eq!(iter.next(), Some(3));
+    }
+
+    #[test]
+    fn to_array_checked_sum() {
+        assert_eq!([1,2,3].iter().copied().to_array_checked_sum(6), Ok([1,2,3]));
+        assert_eq!([1,2,3].iter().copied().to_array_checked_sum::<3>(7), Err(SumError::Imbalance(6)));
+        assert_eq!((0..2).to_array_checked_sum::<3>(1), Err(SumError::Length(ToArrayError::TooShort(2, 3))));
+        assert_eq!((0..4).to_array_checked_sum::<3>(3), Err(SumError::Length(ToArrayError::TooLong(3))));
+    }
+
+    #[test]
+    fn to_array_context() {
+        assert_eq!((0..3).to_array_context("batch 0"), Ok([0,1,2]));
+        for (i, len) in [2, 4].iter().enumerate() {
+            let err = (0..*len).to_array_context::<_, 3>(i).unwrap_err();
+            assert_eq!(err.context, i);
+            assert_eq!(err.source, [ToArrayError::TooShort(2, 3), ToArrayError::TooLong(3)][i]);
+        }
+    }
+
+    #[test]
+    fn context_error_source() {
+        use core::error::Error;
+        use std::string::ToString;
+        let err = (0..1).to_array_context::<_, 3>("row 7").unwrap_err();
+        assert_eq!(err.to_string(), "row 7: iterator too short: got 1, expected 3");
+        let source = err.source().unwrap().downcast_ref::<ToArrayError>();
+        assert_eq!(source, Some(&ToArrayError::TooShort(1, 3)));
+    }
+
+    #[test]
+    fn sum_error_display() {
+        use core::error::Error;
+        use std::string::ToString;
+        let err = [1, 2, 3].iter().copied().to_array_checked_sum::<3>(7).unwrap_err();
+        assert_eq!(err.to_string(), "sum mismatch: got 6");
+        assert!(err.source().is_none());
+
+        let err = (0..1).to_array_checked_sum::<3>(0).unwrap_err();
+        assert_eq!(err.to_string(), "iterator too short: got 1, expected 3");
+        assert_eq!(err.source().unwrap().downcast_ref::<ToArrayError>(), Some(&ToArrayError::TooShort(1, 3)));
+    }
+
+    #[test]
+    fn lazy_array() {
+        let pulled = Cell::new(0);
+        let lazy = LazyArray::<_, 3, _>::new((0..3).inspect(|_| pulled.set(pulled.get() + 1)));
+        assert_eq!(pulled.get(), 0);
+        assert_eq!(lazy.get(), Ok(&[0,1,2]));
+        assert_eq!(lazy.get(), Ok(&[0,1,2]));
+        assert_eq!(pulled.get(), 3);
+
+        let pulled = Cell::new(0);
+        let lazy = LazyArray::<_, 3, _>::new((0..2).inspect(|_| pulled.set(pulled.get() + 1)));
+        assert_eq!(lazy.get(), Err(&ToArrayError::TooShort(2, 3)));
+        assert_eq!(lazy.get(), Err(&ToArrayError::TooShort(2, 3)));
+        assert_eq!(pulled.get(), 2);
+    }
+
+    #[test]
+    fn to_array_flatten() {
+        assert_eq!([Some(1), None, Some(2), Some(3)].iter().copied().to_array_flatten(), Ok([1,2,3]));
+        assert_eq!([None, Some(1), None].iter().copied().to_array_flatten::<2>(), Err(ToArrayError::TooShort(1, 2)));
+        assert_eq!([Some(1), Some(2), None, Some(3)].iter().copied().to_array_flatten::<2>(), Err(ToArrayError::TooLong(2)));
+        assert_eq!([None, None].iter().copied().to_array_flatten::<0>(), Ok([0i32; 0]));
+    }
+
+    #[test]
+    fn running_min_max() {
+        assert_eq!([3,1,2].iter().copied().to_running_min_array(), Ok([3,1,1]));
+        assert_eq!([3,1,2,0,5].iter().copied().to_running_min_array(), Ok([3,1,1,0,0]));
+        assert_eq!([1,3,2,4].iter().copied().to_running_max_array(), Ok([1,3,3,4]));
+        assert_length_errors(|n| (0..n).to_running_min_array::<3>());
+        assert_length_errors(|n| (0..n).to_running_max_array::<3>());
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn to_ndarray() {
+        assert_eq!((0..3).to_ndarray1::<3>(), Ok(ndarray::arr1(&[0,1,2])));
+        assert_length_errors(|n| (0..n).to_ndarray1::<3>());
+
+        assert_eq!((0..6).to_ndarray2::<2, 3>(), Ok(ndarray::arr2(&[[0,1,2], [3,4,5]])));
+        assert_eq!((0..5).to_ndarray2::<2, 3>(), Err(ToArrayError::TooShort(5, 6)));
+        assert_eq!((0..7).to_ndarray2::<2, 3>(), Err(ToArrayError::TooLong(6)));
+    }
+
+    #[test]
+    fn take_bits_array() {
+        let bits = [true, false, true, true, false, false, false, true];
+        assert_eq!(bits.iter().copied().take_bits_array(), Ok([0b1011_0001]));
+        assert_eq!(bits.iter().copied().take_bits_array_lsb(), Ok([0b1000_1101]));
+
+        let mut iter = bits.iter().copied().cycle().take(20);
+        assert_eq!(iter.take_bits_array(), Ok([0b1011_0001, 0b1011_0001]));
+        assert_eq!(iter.take_bits_array::<1>(), Err(ToArrayError::TooShort(4, 8)));
+    }
+
+    #[test]
+    fn to_array_with_order() {
+        assert_eq!([30,10,20].iter().copied().to_array_with_order(), Ok(([30,10,20], [1,2,0])));
+        assert_eq!([2,1,2,1].iter().copied().to_array_with_order(), Ok(([2,1,2,1], [1,3,0,2])));
+        assert_length_errors(|n| (0..n).to_array_with_order::<3>());
+    }
+
+    #[test]
+    fn to_array_if_sorted() {
+        assert_eq!([1,1,2].iter().copied().to_array_if_sorted(), Ok([1,1,2]));
+        assert_eq!([1,2,1].iter().copied().to_array_if_sorted::<3>(), Err(ToArrayError::NotSorted(2)));
+        assert_eq!([1.0, f64::NAN].iter().copied().to_array_if_sorted::<2>(), Err(ToArrayError::NotSorted(1)));
+        assert_length_errors(|n| (0..n).to_array_if_sorted::<3>());
+
+        let mut iter = [3,2,5,6].iter().copied();
+        assert_eq!(iter.by_ref().to_array_if_sorted::<4>(), Err(ToArrayError::NotSorted(1)));
+        assert_eq!(iter.next(), Some(5));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn to_array_by_keys() {
+        use std::collections::BTreeMap;
+        let map: BTreeMap<_, _> = [(3, "c"), (1, "a"), (2, "b")].iter().copied().collect();
+        assert_eq!(super::to_array_by_keys(&map), Ok(["a", "b", "c"]));
+        assert_eq!(super::to_array_by_keys::<_, _, 4>(&map), Err(ToArrayError::TooShort(3, 4)));
+        assert_eq!(super::to_array_by_keys::<_, _, 2>(&map), Err(ToArrayError::TooLong(2)));
+    }
+
+    #[test]
+    fn to_array_counts() {
+        assert_eq!((0..6).to_array_counts(|x| x % 2), Ok(([0,1,2,3,4,5], [3,3])));
+        assert_eq!((0..6).to_array_counts(|&x| x), Ok(([0,1,2,3,4,5], [1,1,1])));
+        assert_eq!((0..5).to_array_counts::<_, 6, 2>(|x| x % 2), Err(ToArrayError::TooShort(5, 6)));
+        assert_eq!((0..7).to_array_counts::<_, 6, 2>(|x| x % 2), Err(ToArrayError::TooLong(6)));
+    }
+
+    #[test]
+    fn fill_optional() {
+        let mut buf = [None; 4];
+        let mut iter = 0..7;
+        assert_eq!(iter.fill_optional(&mut buf), 4);
+        assert_eq!(buf, [Some(0), Some(1), Some(2), Some(3)]);
+        assert_eq!(iter.fill_optional(&mut buf), 3);
+        assert_eq!(buf, [Some(4), Some(5), Some(6), None]);
+        assert_eq!(iter.fill_optional(&mut buf), 0);
+        assert_eq!(buf, [None; 4]);
+    }
+
+    #[cfg(feature = "futures")]
+    fn block_on<F: core::future::Future>(fut: F) -> F::Output {
+        use core::task::{Context, Poll};
+        let mut fut = core::pin::pin!(fut);
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        loop {
+            if let Poll::Ready(x) = fut.as_mut().poll(&mut cx) {
+                return x;
+            }
+        }
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn stream_to_array() {
+        use futures::stream;
+        use std::rc::Rc;
+        assert_eq!(block_on(super::stream_to_array(stream::iter(0..3))), Ok([0,1,2]));
+        assert_eq!(block_on(super::stream_to_array::<_, _, 4>(stream::iter(0..3))), Err(ToArrayError::TooShort(3, 4)));
+        assert_eq!(block_on(super::stream_to_array::<_, _, 2>(stream::iter(0..3))), Err(ToArrayError::TooLong(2)));
+
+        let tracker = Rc::new(());
+        let items = vec![tracker.clone(), tracker.clone()];
+        assert!(block_on(super::stream_to_array::<_, _, 3>(stream::iter(items))).is_err());
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn take_array_merge_adjacent() {
+        use std::string::{String, ToString};
+        let concat = |(k, a): (i32, String), (_, b): (i32, String)| (k, a + &b);
+        let items = || [(1, 'a'), (1, 'b'), (2, 'c'), (3, 'd'), (3, 'e')].iter().map(|&(k, c)| (k, c.to_string()));
+
+        let mut iter = items().peekable();
+        assert_eq!(iter.take_array_merge_adjacent(|a, b| a.0 == b.0, concat), Ok([(1, "ab".to_string()), (2, "c".to_string())]));
+        assert_eq!(iter.peek(), Some(&(3, "d".to_string())));
+        assert_eq!(iter.take_array_merge_adjacent::<_, _, 2>(|a, b| a.0 == b.0, concat), Err(ToArrayError::TooShort(1, 2)));
+
+        let mut iter = items().peekable();
+        assert_eq!(iter.take_array_merge_adjacent::<_, _, 4>(|a, b| a.0 == b.0, concat), Err(ToArrayError::TooShort(3, 4)));
+    }
+
+    #[test]
+    fn take_index_array() {
+        let mut iter = 0..10;
+        assert_eq!(iter.take_index_array(|x| x % 2 == 0), Ok([0,2,4]));
+        assert_eq!(iter.next(), Some(5));
+        assert_eq!(iter.take_index_array(|&x| x > 7), Ok([2,3]));
+        assert_eq!((0..10).take_index_array::<_, 3>(|&x| x > 7), Err(ToArrayError::TooShort(2, 3)));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn take_array_yielding() {
+        let mut iter = 0..10;
+        assert_eq!(iter.take_array_yielding::<4>(2), (0..4).take_array());
+        assert_eq!(iter.take_array_yielding::<7>(0), Err(ToArrayError::TooShort(6, 7)));
+
+        let mut yields = 0;
+        assert_eq!(take_array_with_yield(&mut (0..10), 3, || yields += 1), Ok([0,1,2,3,4,5,6]));
+        assert_eq!(yields, 2);
+        let mut yields = 0;
+        assert_eq!(take_array_with_yield::<_, _, 6>(&mut (0..10), 3, || yields += 1), Ok([0,1,2,3,4,5]));
+        assert_eq!(yields, 2);
+        let mut yields = 0;
+        assert_eq!(take_array_with_yield::<_, _, 5>(&mut (0..10), 0, || yields += 1), Ok([0,1,2,3,4]));
+        assert_eq!(yields, 0);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn split_array_boxed() {
+        use std::vec::Vec;
+        let (arr, rest) = (0..10).split_array_boxed().unwrap();
+        assert_eq!(arr, [0,1,2]);
+        assert_eq!(rest.collect::<Vec<_>>(), (3..10).collect::<Vec<_>>());
+
+        let (arr, mut rest) = [1, 2].iter().split_array_boxed::<2>().unwrap();
+        assert_eq!(arr, [&1, &2]);
+        assert!(rest.next().is_none());
+
+        assert!(matches!((0..2).split_array_boxed::<3>(), Err(ToArrayError::TooShort(2, 3))));
+    }
+
+    #[test]
+    fn unfold_to_array() {
+        let fib = |s: &mut (u64, u64), x: Option<u64>| {
+            let v = x.unwrap_or(s.0 + s.1);
+            *s = (s.1, v);
+            Some(v)
+        };
+        assert_eq!([1, 1].iter().copied().unfold_to_array((0, 0), fib), Ok([1,1,2,3,5,8]));
+        assert_eq!([2, 5].iter().copied().unfold_to_array((0, 0), fib), Ok([2,5,7,12]));
+
+        let mut iter = 0..3;
+        let doubled = iter.unfold_to_array::<_, _, _, 4>((), |_, x| x.map(|x| x * 2));
+        assert_eq!(doubled, Err(ToArrayError::TooShort(3, 4)));
+    }
+
+    #[test]
+    fn to_array_no_dups() {
+        assert_eq!([1,2,3].iter().copied().to_array_no_dups(), Ok([1,2,3]));
+        assert_eq!([1,2,1].iter().copied().to_array_no_dups::<3>(), Err(ToArrayError::Duplicate(2)));
+        assert_eq!([1,1,2].iter().copied().to_array_no_dups::<3>(), Err(ToArrayError::Duplicate(1)));
+        assert_length_errors(|n| (0..n).to_array_no_dups::<3>());
+    }
+
+    #[test]
+    fn chunks_marked() {
+        use std::vec::Vec;
+        let vec: Vec<(bool, [i32; 4])> = (0..9).chunks_marked(|| -1).collect();
+        assert_eq!(vec, vec![(false, [0,1,2,3]), (false, [4,5,6,7]), (true, [8,-1,-1,-1])]);
+
+        let vec: Vec<(bool, [i32; 4])> = (0..8).chunks_marked(|| -1).collect();
+        assert_eq!(vec, vec![(false, [0,1,2,3]), (true, [4,5,6,7])]);
+
+        assert_eq!((0..0).chunks_marked::<_, 4>(|| -1).next(), None);
+    }
+
+    #[test]
+    fn to_array_mean() {
+        assert_eq!((1..=4).to_array_mean::<4>(), Ok(([1,2,3,4], 2.5)));
+        assert_eq!([0.5f32, 1.5].iter().copied().to_array_mean(), Ok(([0.5, 1.5], 1.0)));
+        assert_eq!((1u8..=3).to_array_mean::<4>(), Err(ToArrayError::TooShort(3, 4)));
+        assert_eq!((1u8..=5).to_array_mean::<4>(), Err(ToArrayError::TooLong(4)));
+        // integer widths without an Into<f64> impl
+        assert_eq!((1i64..=4).to_array_mean::<4>().map(|(_, mean)| mean), Ok(2.5));
+        assert_eq!([2u64, 4].iter().copied().to_array_stats::<2>().map(|(_, mean, var)| (mean, var)), Ok((3.0, 1.0)));
+        assert_eq!((0usize..3).to_array_smoothed(1.0), Ok([0.0, 1.0, 2.0]));
+    }
+
+    #[test]
+    fn take_array_enumerate_map() {
+        assert_eq!(["a", "b"].iter().take_array_enumerate_map::<_, _, 2>(|i, s| format!("{}:{}", i, s)), Ok(["0:a".into(), "1:b".into()]));
+        let mut iter = 10..15;
+        assert_eq!(iter.take_array_enumerate_map(|i, x| i * x), Ok([0, 11, 24]));
+        assert_eq!(iter.take_array_enumerate_map::<_, _, 3>(|i, x| i * x), Err(ToArrayError::TooShort(2, 3)));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn chunks_vec() {
+        #[derive(Debug, PartialEq)]
+        struct Token(i32);
+        let chunks: Vec<Vec<Token>> = (0..10).map(Token).chunks_vec::<4>().collect();
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), [4, 4, 2]);
+        assert_eq!(chunks[2], [Token(8), Token(9)]);
+
+        assert_eq!((0..8).chunks_vec::<4>().collect::<Vec<_>>(), [vec![0,1,2,3], vec![4,5,6,7]]);
+        assert_eq!((0..0).chunks_vec::<4>().next(), None);
+    }
+
+    #[test]
+    fn try_to_array_retry() {
+        use std::vec::Vec;
+        assert_eq!(vec![Ok(1), Ok(2)].into_iter().try_to_array::<2>(), Ok(Ok::<_, &str>([1,2])));
+        assert_eq!(vec![Ok(1), Err("boom"), Ok(3)].into_iter().try_to_array::<3>(), Ok(Err("boom")));
+        assert_eq!(vec![Ok::<_, &str>(1)].into_iter().try_to_array::<2>(), Err(ToArrayError::TooShort(1, 2)));
+        assert_eq!(vec![Ok::<_, &str>(1), Ok(2)].into_iter().try_to_array::<1>(), Err(ToArrayError::TooLong(1)));
+
+        let mut attempts = 0;
+        let mut make_iter = || {
+            attempts += 1;
+            let v: Vec<Result<i32, &str>> = if attempts < 3 { vec![Ok(1), Err("flaky")] } else { vec![Ok(1), Ok(2)] };
+            v.into_iter()
+        };
+        let first = make_iter();
+        assert_eq!(first.try_to_array_retry::<_, 2>(2, make_iter), Ok(Ok([1,2])));
+        assert_eq!(attempts, 3);
+
+        let flaky = || vec![Ok(1), Err("flaky")].into_iter();
+        assert_eq!(flaky().try_to_array_retry::<_, 2>(1, flaky), Ok(Err("flaky")));
+
+        // fails twice, then has the wrong length, which stops the retries
+        let mut attempts = 0;
+        let mut make_iter = || {
+            attempts += 1;
+            let v: Vec<Result<i32, &str>> = if attempts < 3 { vec![Ok(1), Err("flaky")] } else { vec![Ok(1)] };
+            v.into_iter()
+        };
+        let first = make_iter();
+        assert_eq!(first.try_to_array_retry::<_, 2>(5, make_iter), Err(ToArrayError::TooShort(1, 2)));
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn take_array_paragraph() {
+        let mut iter = [1,2,0,3,0,0,4,5,6,7].iter().copied();
+        assert_eq!(iter.take_array_paragraph(|&x| x == 0), MaybePartial::Partial([1,2,0], 2));
+        assert_eq!(iter.take_array_paragraph(|&x| x == 0), MaybePartial::Partial([3,0,0], 1));
+        assert_eq!(iter.take_array_paragraph(|&x| x == 0), MaybePartial::Partial([0,0,0], 0));
+        assert_eq!(iter.take_array_paragraph(|&x| x == 0), MaybePartial::Full([4,5,6]));
+        assert_eq!(iter.take_array_paragraph(|&x| x == 0), MaybePartial::Partial([7,0,0], 1));
+        assert_eq!(iter.take_array_paragraph::<_, 3>(|&x| x == 0), MaybePartial::Empty);
+    }
+
+    #[test]
+    fn take_array_pad_masked() {
+        assert_eq!((0..3).take_array_pad_masked::<5>(9), ([0,1,2,9,9], 0b11000));
+        assert_eq!((0..5).take_array_pad_masked::<5>(9), ([0,1,2,3,4], 0));
+        assert_eq!((0..0).take_array_pad_masked::<2>(9), ([9,9], 0b11));
+        let (arr, mask) = (0..1).take_array_pad_masked::<64>(7);
+        assert_eq!((arr[0], arr[63], mask), (0, 7, !1));
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn take_array_logged() {
+        use std::cell::Cell;
+
+        std::thread_local! {
+            static RECORDS: Cell<(usize, usize)> = const { Cell::new((0, 0)) };
+        }
+
+        struct CountingLogger;
+
+        impl log::Log for CountingLogger {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                RECORDS.with(|r| {
+                    let (trace, debug) = r.get();
+                    match record.level() {
+                        log::Level::Trace => r.set((trace + 1, debug)),
+                        log::Level::Debug => r.set((trace, debug + 1)),
+                        _ => {}
+                    }
+                });
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: CountingLogger = CountingLogger;
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Trace);
+
+        assert_eq!((0..5).take_array_logged(), Ok([0,1,2,3,4]));
+        assert_eq!(RECORDS.with(|r| r.replace((0, 0))), (5, 1));
+        assert_eq!((0..2).take_array_logged::<5>(), Err(ToArrayError::TooShort(2, 5)));
+        assert_eq!(RECORDS.with(|r| r.replace((0, 0))), (2, 1));
+    }
+
+    #[test]
+    fn to_array_stats() {
+        let (arr, mean, variance) = [2,4,4,4,5,5,7,9].iter().copied().to_array_stats::<8>().unwrap();
+        assert_eq!(arr, [2,4,4,4,5,5,7,9]);
+        assert!((mean - 5.0).abs() < 1e-12);
+        assert!((variance - 4.0).abs() < 1e-12);
+
+        let (_, mean, variance) = [1e9 + 4.0, 1e9 + 7.0, 1e9 + 13.0, 1e9 + 16.0].iter().copied().to_array_stats::<4>().unwrap();
+        assert!((mean - (1e9 + 10.0)).abs() < 1e-6);
+        assert!((variance - 22.5).abs() < 1e-6);
+
+        assert_eq!((0u8..3).to_array_stats::<4>(), Err(ToArrayError::TooShort(3, 4)));
+        assert_eq!((0u8..5).to_array_stats::<4>(), Err(ToArrayError::TooLong(4)));
+    }
+
+    #[test]
+    fn take_array_sparse() {
+        let updates = [(1, 'x'), (3, 'y')];
+        assert_eq!([' '; 4].iter().copied().take_array_sparse(|| ' ', updates.iter().copied()), [' ', 'x', ' ', 'y']);
+        assert_eq!(core::iter::empty().take_array_sparse::<_, _, 4>(|| ' ', updates.iter().copied()), [' ', 'x', ' ', 'y']);
+
+        let mut iter = 10..15;
+        assert_eq!(iter.take_array_sparse(|| 0, [(0, 1), (9, 2), (0, 3)].iter().copied()), [3, 11]);
+        assert_eq!(iter.next(), Some(12));
+        assert_eq!((0..1).take_array_sparse(|| 5, core::iter::empty()), [0, 5, 5]);
+    }
+
+    #[test]
+    fn take_char_array_matching() {
+        let is_hex = |c: char| c.is_ascii_hexdigit();
+        assert_eq!("abc".chars().take_char_array_matching(is_hex), Ok(['a', 'b', 'c']));
+        assert_eq!("abz".chars().take_char_array_matching::<_, 3>(is_hex), Err(ToArrayError::InvalidChar(2, 'z')));
+        assert_eq!("ab".chars().take_char_array_matching::<_, 3>(is_hex), Err(ToArrayError::TooShort(2, 3)));
+
+        let mut iter = "00ff1".chars();
+        assert_eq!(iter.take_char_array_matching(is_hex), Ok(['0', '0']));
+        assert_eq!(iter.take_char_array_matching(is_hex), Ok(['f', 'f', '1']));
+    }
+
+    #[test]
+    fn to_array_delta() {
+        assert_eq!([10,13,15].iter().copied().to_array_delta(), Ok(([10,13,15], [10,3,2])));
+        assert_eq!([5,1].iter().copied().to_array_delta(), Ok(([5,1], [5,-4])));
+        assert_length_errors(|n| (0..n).to_array_delta::<3>());
+    }
+
+    #[test]
+    fn map_array_from_iter() {
+        let template = ["a", "b", "c"];
+        assert_eq!(super::map_array_from_iter(&template, 0..3), Ok([0,1,2]));
+        assert_length_errors(|n| super::map_array_from_iter(&template, 0..n));
+    }
+
+    #[test]
+    fn to_array_clamped() {
+        assert_eq!([-5, 3, 100].iter().copied().to_array_clamped(0, 10), Ok([0, 3, 10]));
+        assert_length_errors(|n| (0..n).to_array_clamped::<3>(0, 10));
+    }
+
+    #[test]
+    fn to_array_argmax() {
+        assert_eq!([3,7,2,7].iter().copied().to_array_argmax(), Ok(([3,7,2,7], 1)));
+        assert_eq!([3,2,7,2].iter().copied().to_array_argmin(), Ok(([3,2,7,2], 1)));
+        assert_eq!([0.5, -1.0, 2.0].iter().copied().to_array_argmax(), Ok(([0.5, -1.0, 2.0], 2)));
+        assert_length_errors(|n| (0..n).to_array_argmax::<3>());
+        assert_length_errors(|n| (0..n).to_array_argmin::<3>());
+    }
+
+    #[test]
+    fn to_array_with_lookup() {
+        let map = [("one", 1), ("two", 2), ("three", 3)].iter().copied().to_array_with_lookup::<3>().unwrap();
+        assert_eq!(map.entries, [("one", 1), ("two", 2), ("three", 3)]);
+        assert_eq!(map.get(&"two"), Some(&2));
+        assert_eq!(map.get(&"four"), None);
+        assert_eq!([(1, 'a')].iter().copied().to_array_with_lookup::<2>(), Err(ToArrayError::TooShort(1, 2)));
+    }
+
+    #[test]
+    fn to_array_schema() {
+        let schema: [fn(&char) -> bool; 3] = [char::is_ascii_digit, char::is_ascii_alphabetic, char::is_ascii_digit];
+        assert_eq!("1a2".chars().to_array_schema(schema), Ok(['1', 'a', '2']));
+        assert_eq!("1ab".chars().to_array_schema(schema), Err(ToArrayError::SchemaMismatch(2)));
+        assert_eq!("a12".chars().to_array_schema(schema), Err(ToArrayError::SchemaMismatch(0)));
+        assert_length_errors(|n| "1a23".chars().take(n as usize).to_array_schema(schema));
+    }
+
+    #[test]
+    fn to_array_checked_product() {
+        assert_eq!([2,3,4].iter().copied().to_array_checked_product(), Ok(([2,3,4], Some(24))));
+        assert_eq!([16u8, 16].iter().copied().to_array_checked_product(), Ok(([16, 16], None)));
+        assert_eq!([-3i8, 0, 100].iter().copied().to_array_checked_product(), Ok(([-3, 0, 100], Some(0))));
+        assert_eq!(core::iter::empty::<u8>().to_array_checked_product(), Ok(([], Some(1))));
+        assert_length_errors(|n| (1..n + 1).to_array_checked_product::<3>());
+    }
+
+    #[test]
+    fn split_chunks() {
+        use std::vec::Vec;
+        let groups: Vec<MaybePartial<[i32; 4]>> = [1,2,0,3,4,5,6].iter().copied().split_chunks(0, || -1).collect();
+        assert_eq!(groups, vec![MaybePartial::Partial([1,2,-1,-1], 2), MaybePartial::Full([3,4,5,6])]);
+
+        let groups: Vec<MaybePartial<[i32; 2]>> = [1,2,0,0,3,4,5,0].iter().copied().split_chunks(0, || -1).collect();
+        assert_eq!(groups, vec![
+            MaybePartial::Full([1,2]),
+            MaybePartial::Partial([-1,-1], 0),
+            MaybePartial::Full([3,4]),
+            MaybePartial::Partial([5,-1], 1),
+        ]);
+
+        assert_eq!((0..0).split_chunks::<_, 2>(0, || -1).next(), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn to_array_timestamped() {
+        use std::time::SystemTime;
+        let before = SystemTime::now();
+        let (arr, time) = (0..3).to_array_timestamped().unwrap();
+        let after = SystemTime::now();
+        assert_eq!(arr, [0,1,2]);
+        assert!(before <= time && time <= after);
+        assert_eq!((0..2).to_array_timestamped::<3>(), Err(ToArrayError::TooShort(2, 3)));
+    }
+
+    #[test]
+    fn take_array_dedup_window() {
+        let input = [1,2,1,3,2,4];
+        assert_eq!(input.iter().copied().take_array_dedup_window(2), Ok([1,2,3,4]));
+        assert_eq!(input.iter().copied().take_array_dedup_window(1), Ok([1,2,1,3]));
+        assert_eq!(input.iter().copied().take_array_dedup_window(0), Ok([1,2,1,3]));
+        assert_eq!(input.iter().copied().take_array_dedup_window::<5>(2), Err(ToArrayError::TooShort(4, 5)));
+
+        let mut iter = [5,5,5,6,6,7].iter().copied();
+        assert_eq!(iter.take_array_dedup_window(1), Ok([5,6]));
+        assert_eq!(iter.next(), Some(6));
+    }
+
+    #[test]
+    fn parse_array() {
+        let (header, rest) = (0..10).parse_array::<2>().unwrap();
+        let (body, mut rest) = rest.parse_array::<3>().unwrap();
+        assert_eq!((header, body), ([0,1], [2,3,4]));
+        assert_eq!(rest.next(), Some(5));
+        assert!(matches!(rest.parse_array::<5>(), Err(ToArrayError::TooShort(4, 5))));
+    }
+
+    #[test]
+    fn to_grid_col_major() {
+        use std::rc::Rc;
+        assert_eq!((0..6).to_grid_col_major::<3, 2>(), Ok([[0,3], [1,4], [2,5]]));
+        assert_eq!((0..6).to_grid_col_major::<2, 3>(), Ok([[0,2,4], [1,3,5]]));
+
+        // the column-major grid is the transpose of the row-major one
+        let col = (0..6).to_grid_col_major::<3, 2>().unwrap();
+        let row: [[i32; 3]; 2] = (0..6).to_array_2d().unwrap();
+        for r in 0..3 {
+            for c in 0..2 {
+                assert_eq!(col[r][c], row[c][r]);
+            }
+        }
+        assert_eq!((0..5).to_grid_col_major::<3, 2>(), Err(ToArrayError::TooShort(5, 6)));
+        assert_eq!((0..2).to_grid_col_major::<3, 2>(), Err(ToArrayError::TooShort(2, 6)));
+        assert_eq!((0..7).to_grid_col_major::<3, 2>(), Err(ToArrayError::TooLong(6)));
+
+        let tracker = Rc::new(());
+        let items = vec![tracker.clone(); 5];
+        assert!(items.into_iter().to_grid_col_major::<3, 2>().is_err());
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn take_frame_checked() {
+        let xor = |data: &[u8]| data.iter().fold(0, |acc, x| acc ^ x);
+        let mut iter = [0x12u8, 0x34, 0x26, 0x01, 0x02, 0xff].iter().copied();
+        assert_eq!(iter.take_frame_checked(xor), Ok([0x12, 0x34, 0x26]));
+        assert_eq!(iter.take_frame_checked::<_, 3>(xor), Err(ToArrayError::BadChecksum));
+        assert_eq!(iter.take_frame_checked::<_, 3>(xor), Err(ToArrayError::TooShort(0, 3)));
+        assert_eq!([0u8].iter().copied().take_frame_checked(xor), Ok([0]));
+    }
+
+    #[test]
+    fn to_array_max_run() {
+        assert_eq!([1,1,2,2,2,3].iter().copied().to_array_max_run(), Ok(([1,1,2,2,2,3], 3)));
+        assert_eq!([1,2,3].iter().copied().to_array_max_run(), Ok(([1,2,3], 1)));
+        assert_eq!([4,4].iter().copied().to_array_max_run(), Ok(([4,4], 2)));
+        assert_eq!((0..0).to_array_max_run(), Ok(([], 0)));
+        assert_length_errors(|n| (0..n).to_array_max_run::<3>());
+    }
+
+    #[test]
+    fn to_sorted_kv_array() {
+        assert_eq!([(1, 'a'), (3, 'b'), (7, 'c')].iter().copied().to_sorted_kv_array(), Ok([(1, 'a'), (3, 'b'), (7, 'c')]));
+        assert_eq!([(1, 'a'), (7, 'b'), (3, 'c')].iter().copied().to_sorted_kv_array::<3>(), Err(ToArrayError::NotSorted(2)));
+        assert_eq!([(1, 'a'), (1, 'b')].iter().copied().to_sorted_kv_array::<2>(), Err(ToArrayError::NotSorted(1)));
+        assert_eq!([(1, 'a')].iter().copied().to_sorted_kv_array::<2>(), Err(ToArrayError::TooShort(1, 2)));
+        assert_eq!([(1, 'a'), (2, 'b')].iter().copied().to_sorted_kv_array::<1>(), Err(ToArrayError::TooLong(1)));
+    }
+
+    #[test]
+    fn to_array_smoothed() {
+        assert_eq!([4.0, 8.0, 0.0, 2.0].iter().copied().to_array_smoothed(0.5), Ok([4.0, 6.0, 3.0, 2.5]));
+        assert_eq!([1u8, 9].iter().copied().to_array_smoothed(1.0), Ok([1.0, 9.0]));
+        assert_eq!([1.0, 2.0].iter().copied().to_array_smoothed::<3>(0.5), Err(ToArrayError::TooShort(2, 3)));
+        assert_eq!([1.0, 2.0].iter().copied().to_array_smoothed::<1>(0.5), Err(ToArrayError::TooLong(1)));
+    }
 
-        let mut iter = 0..8;
-        assert_eq!(iter.take_array_pad(4), [0,1,2,3,4]);
-        assert_eq!(iter.take_array_pad(4), [5,6,7,4,4]);
-        assert_eq!(iter.take_array_pad(4), [4,4,4,4,4]);
+    #[test]
+    fn to_array_peaks() {
+        assert_eq!([1,3,2,5,4].iter().copied().to_array_peaks(), Ok(([1,3,2,5,4], [false, true, false, true, false])));
+        assert_eq!([5,1,1,2].iter().copied().to_array_peaks(), Ok(([5,1,1,2], [false; 4])));
+        assert_eq!([1,2,2,1].iter().copied().to_array_peaks(), Ok(([1,2,2,1], [false; 4])));
+        assert_length_errors(|n| (0..n).to_array_peaks::<3>());
     }
 
     #[test]
-    fn array_of_vecs() {
+    fn to_array_ranks() {
+        assert_eq!([30,10,20].iter().copied().to_array_ranks(), Ok(([30,10,20], [2,0,1])));
+        assert_eq!([5,1,5,1].iter().copied().to_array_ranks(), Ok(([5,1,5,1], [2,0,3,1])));
+        assert_length_errors(|n| (0..n).to_array_ranks::<3>());
+    }
+
+    #[test]
+    fn to_array_alternating() {
+        assert_eq!([1,-2,3,-4].iter().copied().to_array_alternating(), Ok([1,-2,3,-4]));
+        assert_eq!([-1.5, 2.0].iter().copied().to_array_alternating(), Ok([-1.5, 2.0]));
+        assert_eq!([1,2,-3].iter().copied().to_array_alternating::<3>(), Err(ToArrayError::NotAlternating(1)));
+        assert_eq!([1,0,-3].iter().copied().to_array_alternating::<3>(), Err(ToArrayError::NotAlternating(1)));
+        assert_eq!([0].iter().copied().to_array_alternating::<1>(), Err(ToArrayError::NotAlternating(0)));
+        assert_eq!([1,-1].iter().copied().to_array_alternating::<3>(), Err(ToArrayError::TooShort(2, 3)));
+        assert_eq!([1,-1,1].iter().copied().to_array_alternating::<2>(), Err(ToArrayError::TooLong(2)));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn to_array_rle() {
+        assert_eq!([1,1,2,3,3,3].iter().copied().to_array_rle(), Ok(([1,1,2,3,3,3], vec![(1,2), (2,1), (3,3)])));
+        assert_eq!((0..0).to_array_rle(), Ok(([], vec![])));
+        assert_length_errors(|n| (0..n).to_array_rle::<3>());
+    }
+
+    /// Checks the usual length errors of a collector into `[_; 3]`: `collect(n)` is
+    /// given an iterator of n elements, and must fail for 2 and 4.
+    fn assert_length_errors<R, F>(collect: F) where F: Fn(i32) -> Result<R, ToArrayError> {
+        assert_eq!(collect(2).err(), Some(ToArrayError::TooShort(2, 3)));
+        assert_eq!(collect(4).err(), Some(ToArrayError::TooLong(3)));
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct DropCounter<'a>(Option<&'a Cell<usize>>);
+
+    impl Drop for DropCounter<'_> {
+        fn drop(&mut self) {
+            if let Some(drops) = self.0 {
+                drops.set(drops.get() + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn panicking_iterator_drops_prefix() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let drops = Cell::new(0);
+        let panicking = || (0..).map(|i| if i < 3 { DropCounter(Some(&drops)) } else { panic!("boom") });
+
+        assert!(catch_unwind(AssertUnwindSafe(|| panicking().take_array::<5>())).is_err());
+        assert_eq!(drops.get(), 3);
+
+        drops.set(0);
+        assert!(catch_unwind(AssertUnwindSafe(|| panicking().take_array_partial::<_, 5>(DropCounter::default))).is_err());
+        assert_eq!(drops.get(), 3);
+
+        drops.set(0);
+        assert!(catch_unwind(AssertUnwindSafe(|| panicking().take_array_default::<5>())).is_err());
+        assert_eq!(drops.get(), 3);
+
+        drops.set(0);
+        assert!(catch_unwind(AssertUnwindSafe(|| panicking().take_array_pad::<5>(DropCounter(None)))).is_err());
+        assert_eq!(drops.get(), 3);
+    }
+
+    #[test]
+    fn panicking_padding_drops_prefix() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let drops = Cell::new(0);
+        let mut pads = 0;
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            (0..2).map(|_| DropCounter(Some(&drops))).take_array_partial::<_, 5>(|| {
+                pads += 1;
+                if pads > 1 {
+                    panic!("boom");
+                }
+                DropCounter(Some(&drops))
+            })
+        }));
+        assert!(res.is_err());
+        assert_eq!(drops.get(), 3);
+    }
+
+    #[test]
+    fn to_array_pairs() {
+        let (arr, pairs) = (1..4).to_array_pairs::<3>().unwrap();
+        assert_eq!(arr, [1,2,3]);
+        assert_eq!(pairs.collect::<std::vec::Vec<_>>(), [(1,2), (2,3)]);
+        let (arr, mut pairs) = (0..1).to_array_pairs::<1>().unwrap();
+        assert_eq!(arr, [0]);
+        assert_eq!(pairs.next(), None);
+        assert_length_errors(|n| (0..n).to_array_pairs::<3>());
+    }
+
+    #[test]
+    fn top_n_array_by_key() {
+        use std::rc::Rc;
+
+        #[derive(Debug, PartialEq)]
+        struct Player { name: &'static str, score: u32 }
+
+        let players = [("ann", 7), ("bob", 12), ("cat", 3), ("dan", 9), ("eve", 15), ("fay", 1)]
+            .iter().map(|&(name, score)| Player { name, score });
+        let top: [Player; 3] = players.clone().top_n_array_by_key(|p| p.score).unwrap();
+        assert_eq!(top.map(|p| p.name), ["eve", "bob", "dan"]);
+        let all: [Player; 6] = players.clone().top_n_array_by_key(|p| p.score).unwrap();
+        assert_eq!(all.map(|p| p.score), [15, 12, 9, 7, 3, 1]);
+        assert_eq!(players.clone().top_n_array_by_key::<_, _, 0>(|p| p.score), Ok([]));
+        assert_eq!(players.top_n_array_by_key::<_, _, 7>(|p| p.score), Err(ToArrayError::TooShort(6, 7)));
+
+        let tracker = Rc::new(());
+        let top: [(u32, Rc<()>); 2] = (0..10).map(|i| (i, tracker.clone())).top_n_array_by_key(|&(i, _)| i).unwrap();
+        assert_eq!([top[0].0, top[1].0], [9, 8]);
+        assert_eq!(Rc::strong_count(&tracker), 3);
+    }
+
+    #[test]
+    fn to_array_first_dup() {
+        assert_eq!([1,2,3,2].iter().copied().to_array_first_dup(), Ok(([1,2,3,2], Some((1, 3)))));
+        assert_eq!([1,2,3].iter().copied().to_array_first_dup(), Ok(([1,2,3], None)));
+        assert_eq!([1,2,2,1].iter().copied().to_array_first_dup(), Ok(([1,2,2,1], Some((1, 2)))));
+        assert_length_errors(|n| (0..n).to_array_first_dup::<3>());
+    }
+
+    #[test]
+    fn to_array_exact_fails_fast() {
+        let mut huge = 0..1_000_000;
+        assert_eq!(huge.by_ref().to_array_exact::<5>(), Err(ToArrayError::TooLong(5)));
+        assert_eq!(huge.next(), Some(0));
+
+        let mut short = 0..3;
+        assert_eq!(short.by_ref().to_array_exact::<5>(), Err(ToArrayError::TooShort(3, 5)));
+        assert_eq!(short.next(), Some(0));
+
+        assert_eq!((0..5).to_array_exact(), Ok([0, 1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn to_array_ignores_size_hint() {
+        // claims five elements but only has three
+        struct Liar(core::ops::Range<i32>);
+        impl Iterator for Liar {
+            type Item = i32;
+            fn next(&mut self) -> Option<i32> {
+                self.0.next()
+            }
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (5, Some(5))
+            }
+        }
+        assert_eq!(Liar(0..3).to_array(), Ok([0, 1, 2]));
+        assert_eq!(Liar(0..3).to_array::<5>(), Err(ToArrayError::TooShort(3, 5)));
+        assert_eq!(Liar(0..7).to_array::<5>(), Err(ToArrayError::TooLong(5)));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn to_array_lazy_tail() {
+        let (arr, tail) = (0..5).to_array_lazy_tail::<2>().unwrap();
+        assert_eq!(arr, [0,1]);
+        assert_eq!(tail(), vec![2,3,4]);
+
+        let pulled = Cell::new(0);
+        let (arr, tail) = (0..5).inspect(|_| pulled.set(pulled.get() + 1)).to_array_lazy_tail::<2>().unwrap();
+        assert_eq!(arr, [0,1]);
+        drop(tail);
+        assert_eq!(pulled.get(), 2);
+
+        assert!(matches!((0..1).to_array_lazy_tail::<2>(), Err(ToArrayError::TooShort(1, 2))));
+    }
+
+    #[test]
+    fn take_array_back() {
+        let mut bytes = [1u8, 2, 3, 4, 5, 6, 7].iter().copied();
+        assert_eq!(bytes.take_array_back(), Ok([4, 5, 6, 7]));
+        assert_eq!(bytes.take_array(), Ok([1, 2]));
+        assert_eq!(bytes.take_array_back::<2>(), Err(ToArrayError::TooShort(1, 2)));
+        assert_eq!(bytes.next(), None);
+
+        use std::rc::Rc;
+        let tracker = Rc::new(());
+        let mut it = IntoIterator::into_iter([tracker.clone(), tracker.clone()]);
+        assert!(matches!(it.take_array_back::<3>(), Err(ToArrayError::TooShort(2, 3))));
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn to_sorted_array_total() {
+        let sorted = [3.0, f64::NAN, -0.0, 0.0, -1.5, f64::INFINITY].iter().copied().to_sorted_array_total::<6>().unwrap();
+        assert_eq!(sorted[..5], [-1.5, -0.0, 0.0, 3.0, f64::INFINITY]);
+        assert!(sorted[1].is_sign_negative() && sorted[2].is_sign_positive());
+        assert!(sorted[5].is_nan());
+
+        let sorted = [0.0, -f64::NAN, -0.0].iter().copied().to_sorted_array_total::<3>().unwrap();
+        assert!(sorted[0].is_nan());
+        assert_eq!(sorted.map(f64::to_bits), [(-f64::NAN).to_bits(), (-0.0f64).to_bits(), 0.0f64.to_bits()]);
+
+        assert_eq!([1.0].iter().copied().to_sorted_array_total::<2>(), Err(ToArrayError::TooShort(1, 2)));
+        assert_eq!([1.0, 2.0, 3.0].iter().copied().to_sorted_array_total::<2>(), Err(ToArrayError::TooLong(2)));
+    }
+
+    #[test]
+    fn chunks_size_hint() {
+        let chunks = (0..30).chunks::<_, 6>(|| 0);
+        assert_eq!(chunks.size_hint(), (5, Some(5)));
+        let mut chunks = (0..31).chunks::<_, 6>(|| 0);
+        assert_eq!(chunks.size_hint(), (6, Some(6)));
+        chunks.next();
+        assert_eq!(chunks.size_hint(), (5, Some(5)));
+        assert_eq!(chunks.collect::<std::vec::Vec<_>>().len(), 5);
+        assert_eq!((0..0).chunks::<_, 6>(|| 0).size_hint(), (0, Some(0)));
+        assert_eq!((0..).chunks::<_, 6>(|| 0).size_hint(), (usize::MAX.div_ceil(6), None));
+        assert_eq!((0..30).filter(|_| true).chunks::<_, 6>(|| 0).size_hint(), (0, Some(5)));
+    }
+
+    #[test]
+    fn to_array_minmax() {
+        assert_eq!([3,1,4,1,5].iter().copied().to_array_minmax(), Ok(([3,1,4,1,5], 1, 5)));
+        assert_eq!([2.5].iter().copied().to_array_minmax(), Ok(([2.5], 2.5, 2.5)));
+        assert_length_errors(|n| (0..n).to_array_minmax::<3>());
+    }
+
+    #[test]
+    fn full_chunks() {
+        let mut chunks = (0..9).full_chunks::<4>();
+        assert_eq!(chunks.next(), Some([0,1,2,3]));
+        assert_eq!(chunks.next(), Some([4,5,6,7]));
+        assert_eq!(chunks.next(), None);
+        assert_eq!(chunks.next(), None);
+        assert_eq!(chunks.into_remainder().collect::<std::vec::Vec<_>>(), [8]);
+
+        let mut chunks = (0..0).full_chunks::<3>();
+        assert_eq!(chunks.next(), None);
+        assert_eq!(chunks.into_remainder().next(), None);
+
+        let mut chunks = (0..6).full_chunks::<3>();
+        assert_eq!(chunks.by_ref().collect::<std::vec::Vec<_>>(), [[0,1,2], [3,4,5]]);
+        assert_eq!(chunks.into_remainder().next(), None);
+
+        let mut chunks = "abcdef".chars().full_chunks::<4>();
+        assert_eq!(chunks.next(), Some(['a','b','c','d']));
+        assert_eq!(chunks.next(), None);
+        assert_eq!(chunks.into_remainder().collect::<std::string::String>(), "ef");
+
+        use std::rc::Rc;
+        let tracker = Rc::new(());
+        let mut chunks = std::iter::repeat(tracker.clone()).take(3).full_chunks::<4>();
+        assert_eq!(chunks.next(), None);
+        let mut remainder = chunks.into_remainder();
+        remainder.next();
+        drop(remainder);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn to_array_with_sorted_unique() {
+        assert_eq!([3,1,3,2,1].iter().copied().to_array_with_sorted_unique(), Ok(([3,1,3,2,1], vec![1,2,3])));
+        assert_eq!((0..0).to_array_with_sorted_unique(), Ok(([], vec![])));
+        assert_length_errors(|n| (0..n).to_array_with_sorted_unique::<3>());
+    }
+
+    #[test]
+    fn try_take_array() {
+        let mut results = [Ok(1), Ok(2), Err("boom"), Ok(4)].iter().copied();
+        assert_eq!(results.try_take_array::<4>(), Ok(Err("boom")));
+        assert_eq!(results.next(), Some(Ok(4)));
+
+        let mut results = [Ok(1), Ok(2), Ok(3)].iter().copied();
+        assert_eq!(results.try_take_array::<2>(), Ok(Ok::<_, &str>([1, 2])));
+        assert_eq!(results.try_take_array::<2>(), Err(ToArrayError::TooShort(1, 2)));
+
+        use std::rc::Rc;
+        let tracker = Rc::new(());
+        let mut results = IntoIterator::into_iter([Ok(tracker.clone()), Ok(tracker.clone()), Err("boom"), Ok(tracker.clone())]);
+        assert!(matches!(results.try_take_array::<4>(), Ok(Err("boom"))));
+        assert_eq!(Rc::strong_count(&tracker), 2);
+        drop(results);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn take_array_join() {
+        assert_eq!((0..3).take_array_join(|i| 100 + i), Ok([0, 101, 1, 103, 2]));
+        let mut iter = 0..5;
+        assert_eq!(iter.take_array_join(|_| -1), Ok([0, -1, 1, -1]));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!((0..2).take_array_join::<_, 5>(|_| -1), Err(ToArrayError::TooShort(4, 5)));
+    }
+
+    #[test]
+    fn error_display() {
+        use std::string::ToString;
+        assert_eq!(ToArrayError::TooShort(5, 6).to_string(), "iterator too short: got 5, expected 6");
+        assert_eq!(ToArrayError::TooLong(4).to_string(), "iterator too long: expected 4");
+        assert_eq!(ToArrayError::NotSorted(2).to_string(), "element 2 is out of order");
+        assert_eq!(ToArrayError::Duplicate(3).to_string(), "element 3 is a duplicate");
+        assert_eq!(ToArrayError::InvalidChar(1, 'x').to_string(), "invalid character 'x' at index 1");
+        assert_eq!(ToArrayError::SchemaMismatch(0).to_string(), "element 0 does not match the schema");
+        assert_eq!(ToArrayError::BadChecksum.to_string(), "checksum mismatch");
+        assert_eq!(ToArrayError::NotAlternating(4).to_string(), "element 4 does not alternate in sign");
+
+        let boxed: std::boxed::Box<dyn core::error::Error> = ToArrayError::TooLong(4).into();
+        assert_eq!(boxed.to_string(), "iterator too long: expected 4");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn to_array_entropy() {
+        assert_eq!([0,1,2,3].iter().copied().to_array_entropy(), Ok(([0,1,2,3], 2.0)));
+        assert_eq!([5,5,5,5].iter().copied().to_array_entropy(), Ok(([5,5,5,5], 0.0)));
+        assert_eq!(['a','a','b','b'].iter().copied().to_array_entropy(), Ok((['a','a','b','b'], 1.0)));
+        assert_eq!((0..0).to_array_entropy(), Ok(([], 0.0)));
+        assert_length_errors(|n| (0..n).to_array_entropy::<3>());
+    }
+
+    #[test]
+    fn take_array_recover() {
+        let mut iter = 0..5;
+        assert_eq!(iter.take_array_recover().ok(), Some([0,1,2]));
+        let (err, taken) = iter.take_array_recover::<3>().unwrap_err();
+        assert_eq!(err, ToArrayError::TooShort(2, 3));
+        assert_eq!(taken.len(), 2);
+        assert_eq!(taken.collect::<std::vec::Vec<_>>(), [3,4]);
+
+        assert_eq!((0..3).to_array_recover().ok(), Some([0,1,2]));
+        match (0..2).to_array_recover::<3>() {
+            Err(Recovered::TooShort(taken)) => assert_eq!(taken.collect::<std::vec::Vec<_>>(), [0,1]),
+            _ => panic!("expected TooShort")
+        }
+        match (0..5).to_array_recover::<3>() {
+            Err(Recovered::TooLong(arr, extra)) => assert_eq!((arr, extra), ([0,1,2], 3)),
+            _ => panic!("expected TooLong")
+        }
+    }
+
+    #[test]
+    fn recover_drops_each_element_once() {
+        let drops = Cell::new(0);
+        let counters = |n| (0..n).map(|_| DropCounter(Some(&drops)));
+
+        let (_, mut taken) = counters(3).take_array_recover::<5>().unwrap_err();
+        let first = taken.next();
+        assert_eq!(drops.get(), 0);
+        drop(taken);
+        assert_eq!(drops.get(), 2);
+        drop(first);
+        assert_eq!(drops.get(), 3);
+
+        drops.set(0);
+        match counters(2).to_array_recover::<3>() {
+            Err(Recovered::TooShort(taken)) => drop(taken),
+            _ => panic!("expected TooShort")
+        }
+        assert_eq!(drops.get(), 2);
+
+        drops.set(0);
+        match counters(5).to_array_recover::<3>() {
+            Err(Recovered::TooLong(arr, extra)) => {
+                drop(extra);
+                assert_eq!(drops.get(), 1);
+                drop(arr);
+            },
+            _ => panic!("expected TooLong")
+        }
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    fn take_array_partial_indexed() {
+        assert_eq!((0..2).take_array_partial_indexed::<_, 4>(|i| i as i32 * 10), MaybePartial::Partial([0,1,20,30], 2));
+        assert_eq!((0..4).take_array_partial_indexed::<_, 4>(|_| panic!("no padding needed")), MaybePartial::Full([0,1,2,3]));
+        assert_eq!((0..0).take_array_partial_indexed::<_, 4>(|_| panic!("no padding needed")), MaybePartial::Empty);
+
+        let mut padded = std::vec::Vec::new();
+        let chunks: std::vec::Vec<[usize; 3]> = (0..4).chunks_indexed(|i| { padded.push(i); 100 + i }).collect();
+        assert_eq!(chunks, [[0,1,2], [3,101,102]]);
+        assert_eq!(padded, [1, 2]);
+    }
+
+    #[test]
+    fn windows() {
+        assert_eq!((0..5).windows::<3>().collect::<std::vec::Vec<_>>(), [[0,1,2], [1,2,3], [2,3,4]]);
+        assert_eq!((0..3).windows::<3>().collect::<std::vec::Vec<_>>(), [[0,1,2]]);
+        assert_eq!((0..2).windows::<3>().next(), None);
+        assert_eq!((0..3).windows::<1>().collect::<std::vec::Vec<_>>(), [[0], [1], [2]]);
+
+        use std::rc::Rc;
+        let tracker = Rc::new(());
+        let mut windows = IntoIterator::into_iter([(); 5].map(|_| tracker.clone())).windows::<2>();
+        let first = windows.next().unwrap();
+        windows.next();
+        // two left in the source, two in the buffer, and the first window
+        assert_eq!(Rc::strong_count(&tracker), 1 + 2 + 2 + 2);
+        drop(windows);
+        drop(first);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+
+        // a source that resumes after returning None is not read any further
+        let unfused = |items: &'static [Option<i32>]| {
+            let mut items = items.iter().copied();
+            core::iter::from_fn(move || items.next().flatten())
+        };
+        let mut windows = unfused(&[Some(0), None, Some(1), Some(2)]).windows::<2>();
+        assert_eq!(windows.next(), None);
+        assert_eq!(windows.next(), None);
+        let mut windows = unfused(&[Some(0), Some(1), None, Some(2), Some(3)]).windows::<2>();
+        assert_eq!(windows.next(), Some([0, 1]));
+        assert_eq!(windows.next(), None);
+        assert_eq!(windows.next(), None);
+    }
+
+    #[test]
+    fn map_to_array() {
+        use std::string::{String, ToString};
+        let mut iter = 0..5;
+        assert_eq!(iter.map_to_array(|x| x.to_string()), Ok([String::from("0"), String::from("1"), String::from("2")]));
+        assert_eq!(iter.map_to_array::<String, _, 3>(|x| x.to_string()), Err(ToArrayError::TooShort(2, 3)));
+
+        let drops = Cell::new(0);
+        let res = (0..2).map_to_array::<_, _, 3>(|x| (x.to_string(), DropCounter(Some(&drops))));
+        assert!(matches!(res, Err(ToArrayError::TooShort(2, 3))));
+        assert_eq!(drops.get(), 2);
+    }
+
+    #[test]
+    fn zip_to_array() {
+        assert_eq!(super::zip_to_array(0..3, "abc".chars()), Ok([(0,'a'), (1,'b'), (2,'c')]));
+        assert_eq!(super::zip_to_array::<_, _, _, _, 3>(0..5, "ab".chars()), Err(ToArrayError::TooShort(2, 3)));
+        assert_eq!(super::zip_to_array::<_, _, _, _, 3>(0..2, "abcd".chars()), Err(ToArrayError::TooShort(2, 3)));
+        assert_eq!(super::zip_to_array::<_, _, _, _, 2>(0..3, "abc".chars()), Err(ToArrayError::TooLong(2)));
+        assert_eq!(super::zip_to_array(0..2, "abc".chars().filter(|_| true)), Ok([(0,'a'), (1,'b')]));
+
+        let drops = Cell::new(0);
+        let a = (0..5).map(|_| DropCounter(Some(&drops)));
+        let b = (0..2).map(|_| DropCounter(Some(&drops))).filter(|_| true);
+        assert!(matches!(super::zip_to_array::<_, _, _, _, 3>(a, b), Err(ToArrayError::TooShort(2, 3))));
+        // two complete pairs and the stray element from `a`
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn try_chunks() {
         use std::vec::Vec;
-        let v = vec![(1..5).collect::<Vec<i32>>(); 5];
-        let arr = v.into_iter().to_array::<5>().unwrap();
-        for x in &arr {
-            assert_eq!(*x, vec![1i32,2,3,4])
+        assert_eq!((0..8).try_chunks::<4>().collect::<Result<Vec<_>, _>>(), Ok(vec![[0,1,2,3], [4,5,6,7]]));
+        assert_eq!((0..9).try_chunks::<4>().collect::<Result<Vec<_>, _>>(), Err(ToArrayError::TooShortAt { got: 1, expected: 4, offset: 8 }));
+        assert_eq!((0..0).try_chunks::<4>().next(), None);
+
+        let mut chunks = (0..9).try_chunks::<4>();
+        assert_eq!(chunks.next(), Some(Ok([0,1,2,3])));
+        assert_eq!(chunks.next(), Some(Ok([4,5,6,7])));
+        assert_eq!(chunks.next(), Some(Err(ToArrayError::TooShortAt { got: 1, expected: 4, offset: 8 })));
+        assert_eq!(chunks.next(), None);
+
+        let drops = Cell::new(0);
+        let mut chunks = (0..6).map(|_| DropCounter(Some(&drops))).try_chunks::<4>();
+        drop(chunks.next());
+        assert_eq!(drops.get(), 4);
+        assert!(matches!(chunks.next(), Some(Err(ToArrayError::TooShortAt { got: 2, expected: 4, offset: 4 }))));
+        assert_eq!(drops.get(), 6);
+    }
+
+    #[test]
+    fn to_array_2d() {
+        assert_eq!((0..6).to_array_2d::<2, 3>(), Ok([[0,1,2], [3,4,5]]));
+        assert_eq!((0..5).to_array_2d::<2, 3>(), Err(ToArrayError::TooShort(5, 6)));
+        assert_eq!((0..0).to_array_2d::<2, 3>(), Err(ToArrayError::TooShort(0, 6)));
+        assert_eq!((0..7).to_array_2d::<2, 3>(), Err(ToArrayError::TooLong(6)));
+
+        let drops = Cell::new(0);
+        let res = (0..5).map(|_| DropCounter(Some(&drops))).filter(|_| true).to_array_2d::<2, 3>();
+        assert!(matches!(res, Err(ToArrayError::TooShort(5, 6))));
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn chunks_fused_and_clone() {
+        fn assert_fused<I: FusedIterator>(_: &I) {}
+
+        let mut chunks = (0..5).chunks::<_, 2>(|| -1);
+        assert_fused(&chunks);
+        assert_eq!(chunks.next(), Some([0,1]));
+
+        let copy = chunks.clone();
+        assert_eq!(copy.collect::<std::vec::Vec<_>>(), [[2,3], [4,-1]]);
+        assert_eq!(chunks.next(), Some([2,3]));
+        assert_eq!(chunks.next(), Some([4,-1]));
+        assert_eq!(chunks.next(), None);
+        assert_eq!(chunks.next(), None);
+        assert_eq!(chunks.next(), None);
+    }
+
+    #[test]
+    fn rchunks() {
+        use std::vec::Vec;
+        fn manual<const N: usize>(len: i32) -> Vec<[i32; N]> {
+            (0..len).rev().chunks(|| -1).map(|mut chunk: [i32; N]| { chunk.reverse(); chunk }).collect()
+        }
+
+        assert_eq!((0..9).rchunks::<_, 4>(|| -1).collect::<Vec<_>>(), [[5,6,7,8], [1,2,3,4], [-1,-1,-1,0]]);
+        for len in [0, 3, 8, 9] {
+            assert_eq!((0..len).rchunks::<_, 4>(|| -1).collect::<Vec<_>>(), manual::<4>(len));
+            assert_eq!((0..len).rchunks::<_, 3>(|| -1).collect::<Vec<_>>(), manual::<3>(len));
         }
+        assert_eq!((0..3).rchunks::<_, 4>(|| -1).collect::<Vec<_>>(), [[-1,0,1,2]]);
     }
 
     #[test]
-    #[should_panic]
-    fn array_of_vecs_fail() {
+    fn take_array_copy() {
+        let mut iter = 0..10;
+        assert_eq!(iter.take_array_copy(), Ok([0,1,2,3,4]));
+        assert_eq!(iter.take_array_copy(), Ok([5,6,7]));
+        assert_eq!(iter.take_array_copy::<5>(), Err(ToArrayError::TooShort(2, 5)));
+        assert_eq!((0..0).take_array_copy::<0>(), Ok([]));
+
+        let bytes: [u8; 1024] = (0..=255).cycle().take_array_copy().unwrap();
+        assert_eq!(bytes, (0..=255).cycle().take_array::<1024>().unwrap());
+    }
+
+    #[test]
+    fn collect_array() {
+        assert_eq!(super::collect_array::<_, 5>(0..5), Ok([0,1,2,3,4]));
+        assert_eq!(super::collect_array(vec![1, 2, 3]), Ok([1, 2, 3]));
+        assert_eq!(super::collect_array([1, 2]), Ok([1, 2]));
+        assert_eq!(super::collect_array::<_, 6>(0..5), Err(ToArrayError::TooShort(5, 6)));
+        assert_eq!(super::collect_array::<_, 4>(0..5), Err(ToArrayError::TooLong(4)));
+
+        assert_eq!(collect_array!(0..5; 5), Ok([0,1,2,3,4]));
+        assert_eq!(collect_array!(vec![1, 2]; 1 + 1), Ok([1, 2]));
+        assert_eq!(collect_array!(0..5; 6), Err(ToArrayError::TooShort(5, 6)));
+        assert_eq!(collect_array!(0..5; 4), Err(ToArrayError::TooLong(4)));
+        let arr: Result<[u8; 3], _> = collect_array!(b"abc".iter().copied());
+        assert_eq!(arr, Ok(*b"abc"));
+    }
+
+    #[test]
+    fn chunks_partial() {
+        let chunks: std::vec::Vec<_> = (0..9).chunks_partial::<_, 4>(|| 0).collect();
+        assert_eq!(chunks, [MaybePartial::Full([0,1,2,3]), MaybePartial::Full([4,5,6,7]), MaybePartial::Partial([8,0,0,0], 1)]);
+        let chunks: std::vec::Vec<_> = (0..8).chunks_partial::<_, 4>(|| 0).collect();
+        assert_eq!(chunks, [MaybePartial::Full([0,1,2,3]), MaybePartial::Full([4,5,6,7])]);
+        assert_eq!((0..0).chunks_partial::<_, 4>(|| 0).next(), None);
+    }
+
+    #[test]
+    fn take_array_counted() {
+        assert_eq!((0..3).take_array_counted::<5>(), ([0,1,2,0,0], 3));
+        let mut iter = 1..10;
+        assert_eq!(iter.take_array_counted(), ([1,2,3,4], 4));
+        assert_eq!(iter.take_array_counted(), ([5,6,7,8], 4));
+        assert_eq!(iter.take_array_counted(), ([9,0,0,0], 1));
+        assert_eq!(iter.take_array_counted(), ([0,0,0,0], 0));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn to_array_overflow() {
+        assert_eq!((0..4).to_array_overflow(), Ok([0,1,2,3]));
+        assert_eq!((0..7).to_array_overflow::<4>(), Err((ToArrayError::TooLong(4), vec![4,5,6])));
+        assert_eq!((0..2).to_array_overflow::<4>(), Err((ToArrayError::TooShort(2, 4), vec![0,1])));
+        assert_eq!((0..0).to_array_overflow::<4>(), Err((ToArrayError::TooShort(0, 4), vec![])));
+    }
+
+    #[test]
+    fn chunks_len() {
+        assert_eq!((0..30).chunks_default::<6>().len(), 5);
+        assert_eq!((0..31).chunks_default::<6>().len(), 6);
+        assert_eq!((0..0).chunks_default::<6>().len(), 0);
+        let mut chunks = (0..13).chunks_default::<6>();
+        chunks.next();
+        assert_eq!(chunks.len(), 2);
+    }
+
+    #[test]
+    fn chunks_next_back() {
         use std::vec::Vec;
-        let v = vec![(1..5).collect::<Vec<i32>>(); 5];
-        v.into_iter().to_array::<6>().unwrap();
+        let forward: Vec<[i32; 4]> = (0..9).chunks_default().collect();
+        let mut chunks = (0..9).chunks_default::<4>();
+        let mut backward = Vec::new();
+        while let Some(chunk) = chunks.next_back() {
+            backward.push(chunk);
+        }
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(forward, [[0,1,2,3], [4,5,6,7], [8,0,0,0]]);
+
+        assert_eq!((0..8).chunks_default::<4>().rev().collect::<Vec<_>>(), [[4,5,6,7], [0,1,2,3]]);
+
+        let mut chunks = (0..10).chunks::<_, 4>(|| -1);
+        assert_eq!(chunks.next_back(), Some([8,9,-1,-1]));
+        assert_eq!(chunks.next(), Some([0,1,2,3]));
+        assert_eq!(chunks.next_back(), Some([4,5,6,7]));
+        assert_eq!(chunks.next(), None);
+        assert_eq!(chunks.next_back(), None);
     }
 
     #[test]
-    fn chunks_iter() {
+    fn take_array_pad_ref() {
+        #[derive(Debug, PartialEq)]
+        struct Counted<'a>(i32, &'a Cell<usize>);
+
+        impl Clone for Counted<'_> {
+            fn clone(&self) -> Self {
+                self.1.set(self.1.get() + 1);
+                Counted(self.0, self.1)
+            }
+        }
+
+        let clones = Cell::new(0);
+        let pad = Counted(-1, &clones);
+        let items = |n| (0..n).map(|i| Counted(i, &clones));
+
+        let arr: [Counted; 3] = items(5).take_array_pad_ref(&pad);
+        assert_eq!(arr.map(|c| c.0), [0,1,2]);
+        assert_eq!(clones.get(), 0);
+
+        let arr: [Counted; 5] = items(2).take_array_pad_ref(&pad);
+        assert_eq!(arr.map(|c| c.0), [0,1,-1,-1,-1]);
+        assert_eq!(clones.get(), 3);
+
+        clones.set(0);
+        let arr: [Counted; 3] = items(3).to_array_pad(pad.clone()).unwrap();
+        assert_eq!(arr.map(|c| c.0), [0,1,2]);
+        // only the clone passed in above
+        assert_eq!(clones.get(), 1);
+    }
+
+    #[test]
+    fn zero_size_arrays() {
+        assert_eq!((0..5).to_array::<0>(), Err(ToArrayError::TooLong(0)));
+        assert_eq!((0..0).to_array::<0>(), Ok([]));
+        assert_eq!((0..5).filter(|_| true).to_array::<0>(), Err(ToArrayError::TooLong(0)));
+        assert_eq!((0..0).filter(|_| true).to_array::<0>(), Ok([]));
+
+        let mut iter = 0..5;
+        assert_eq!(iter.take_array::<0>(), Ok([]));
+        assert_eq!(iter.take_array_partial::<_, 0>(|| panic!("no padding needed")), MaybePartial::Full([]));
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!((0..0).take_array_partial::<_, 0>(|| 0), MaybePartial::Full([]));
+    }
+
+    #[test]
+    fn step_chunks() {
         use std::vec::Vec;
-        use std::convert::TryInto;
-        for chunk in (0..5).cycle().take(100).chunks_default() {
-            assert_eq!(chunk, [0,1,2,3,4])
+        // S > N skips elements
+        assert_eq!((0..7).step_chunks::<_, 2, 3>(|| -1).collect::<Vec<_>>(), [[0,1], [3,4], [6,-1]]);
+        assert_eq!((0..8).step_chunks::<_, 2, 3>(|| -1).collect::<Vec<_>>(), [[0,1], [3,4], [6,7]]);
+        assert_eq!((0..9).step_chunks::<_, 2, 3>(|| -1).collect::<Vec<_>>(), [[0,1], [3,4], [6,7]]);
+        // S = N is plain chunking
+        assert_eq!((0..5).step_chunks::<_, 2, 2>(|| -1).collect::<Vec<_>>(), (0..5).chunks::<_, 2>(|| -1).collect::<Vec<_>>());
+        assert_eq!((0..4).step_chunks::<_, 2, 2>(|| -1).collect::<Vec<_>>(), [[0,1], [2,3]]);
+        // S < N overlaps
+        assert_eq!((0..5).step_chunks::<_, 3, 1>(|| -1).collect::<Vec<_>>(), [[0,1,2], [1,2,3], [2,3,4], [3,4,-1], [4,-1,-1]]);
+        assert_eq!((0..6).step_chunks::<_, 4, 2>(|| -1).collect::<Vec<_>>(), [[0,1,2,3], [2,3,4,5], [4,5,-1,-1]]);
+        assert_eq!((0..2).step_chunks::<_, 3, 2>(|| -1).collect::<Vec<_>>(), [[0,1,-1]]);
+
+        assert_eq!((0..0).step_chunks::<_, 3, 1>(|| -1).next(), None);
+    }
+
+    #[test]
+    fn array_builder_drops_pushed_prefix() {
+        let drops = Cell::new(0);
+        let mut builder = ArrayBuilder::<_, 4>::new();
+        builder.push(DropCounter(Some(&drops))).unwrap();
+        builder.push(DropCounter(Some(&drops))).unwrap();
+        assert_eq!(builder.len(), 2);
+        assert!(!builder.is_full());
+        let builder = builder.build().unwrap_err();
+        assert_eq!(drops.get(), 0);
+        drop(builder);
+        assert_eq!(drops.get(), 2);
+    }
+
+    #[test]
+    fn array_builder_build() {
+        let mut builder = ArrayBuilder::<_, 3>::default();
+        assert!(builder.is_empty());
+        for i in 0..3 {
+            assert_eq!(builder.push(i), Ok(()));
         }
-        let vec: Vec<usize> = (0..30).collect();
-        let chunks1: Vec<[usize; 6]> = vec.clone().chunks(6).map(|x| x.try_into().unwrap()).collect();
-        let chunks2: Vec<[usize; 6]> = vec.into_iter().chunks_default().collect();
-        assert_eq!(chunks1, chunks2);
-        
-        let vec: Vec<[i32; 4]> = (0..8).chunks(|| -1).collect();
-        assert_eq!(vec,  vec![[0,1,2,3], [4,5,6,7]]);
+        assert!(builder.is_full());
+        assert_eq!(builder.push(3), Err(3));
+        assert_eq!(builder.build().ok(), Some([0, 1, 2]));
 
-        let vec: Vec<[i32; 4]> = (0..9).chunks(|| -1).collect();
-        assert_eq!(vec, vec![[0,1,2,3], [4,5,6,7], [8,-1,-1,-1]]);
+        let drops = Cell::new(0);
+        let mut builder = ArrayBuilder::<_, 2>::new();
+        builder.push(DropCounter(Some(&drops))).unwrap();
+        builder.push(DropCounter(Some(&drops))).unwrap();
+        let arr = builder.build().ok().unwrap();
+        assert_eq!(drops.get(), 0);
+        drop(arr);
+        assert_eq!(drops.get(), 2);
+    }
 
-        let vec: Vec<[i32; 4]> = (0..4).chunks(|| -1).collect();
-        assert_eq!(vec, vec![[0,1,2,3]]);
+    #[test]
+    fn take_array_try_pad() {
+        let mut pad = 10..12;
+        assert_eq!((0..2).take_array_try_pad(|| pad.next()), MaybePartial::Full([0, 1, 10, 11]));
+        assert_eq!(pad.next(), None);
 
-        let vec: Vec<[i32; 4]> = (0..0).chunks(|| -1).collect();
-        assert_eq!(vec, Vec::<[i32; 4]>::new());
+        // pad runs out before N
+        let mut pad = 10..11;
+        assert_eq!((0..2).take_array_try_pad(|| pad.next()), MaybePartial::Partial([0, 1, 10, 0, 0], 2));
+        assert_eq!((0..2).take_array_try_pad(|| None), MaybePartial::Partial([0, 1, 0], 2));
+
+        let mut iter = 0..5;
+        assert_eq!(iter.take_array_try_pad(|| Some(-1)), MaybePartial::Full([0, 1, 2]));
+        assert_eq!(iter.next(), Some(3));
+
+        let mut calls = 0;
+        assert_eq!((0..0).take_array_try_pad::<_, 3>(|| { calls += 1; Some(-1) }), MaybePartial::Full([-1, -1, -1]));
+        assert_eq!(calls, 3);
+
+        // both empty, or padding alone cannot fill the array
+        assert_eq!((0..0).take_array_try_pad::<_, 3>(|| None), MaybePartial::Empty);
+        let mut pad = 10..12;
+        assert_eq!((0..0).take_array_try_pad::<_, 3>(|| pad.next()), MaybePartial::Empty);
+        assert_eq!(pad.next(), None);
+    }
+
+    #[test]
+    fn peek_array() {
+        let mut iter = [1, 2, 3, 4, 5].iter().copied();
+        assert_eq!(iter.peek_array::<3>(), Ok([1, 2, 3]));
+        assert_eq!(iter.peek_array::<3>(), iter.take_array::<3>());
+        assert_eq!(iter.peek_array::<3>(), Err(ToArrayError::TooShort(2, 3)));
+        assert_eq!(iter.take_array(), Ok([4, 5]));
+
+        use std::rc::Rc;
+        let rc = Rc::new(());
+        let iter = core::iter::repeat(rc.clone()).take(2);
+        assert!(iter.peek_array::<3>().is_err());
+        assert_eq!(Rc::strong_count(&rc), 2);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn fill_array_drops_prefix_when_short() {
+        let drops = Cell::new(0);
+        let mut out = [const { MaybeUninit::<DropCounter>::uninit() }; 4];
+        let res = core::iter::repeat(DropCounter(Some(&drops))).take(3).fill_array(&mut out);
+        assert_eq!(res, Err(ToArrayError::TooShort(3, 4)));
+        // the three written clones, plus the original held by `repeat`
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    fn fill_array() {
+        let mut out = [const { MaybeUninit::<std::string::String>::uninit() }; 3];
+        let mut iter = ["a", "b", "c", "d"].iter().map(|&s| std::string::String::from(s));
+        assert_eq!(iter.fill_array(&mut out), Ok(()));
+        let arr = out.map(|el| unsafe { el.assume_init() });
+        assert_eq!(arr, ["a", "b", "c"]);
+        assert_eq!(iter.next().as_deref(), Some("d"));
+
+        let mut out: [MaybeUninit<u8>; 0] = [];
+        assert_eq!((0..3).fill_array(&mut out), Ok(()));
+    }
+
+    #[test]
+    fn try_chunks_reports_offset() {
+        use std::vec::Vec;
+        // a 4-byte header followed by 3-byte frames, misaligned by the trailing two bytes
+        let data = [0u8; 4 + 3 * 17 + 2];
+        let mut iter = data.iter().copied();
+        assert!(iter.take_array::<4>().is_ok());
+        let frames = iter.clone().try_chunks_from::<3>(4).collect::<Vec<_>>();
+        assert_eq!(frames.len(), 18);
+        let err = frames[17].clone().unwrap_err();
+        // the incomplete frame starts at byte 55 of `data`
+        assert_eq!(err, ToArrayError::TooShortAt { got: 2, expected: 3, offset: 55 });
+        assert_eq!(format!("{}", err), "iterator too short in chunk at offset 55: got 2, expected 3");
+
+        // without a base, offsets count from where chunking began
+        let err = iter.try_chunks::<3>().last().unwrap().unwrap_err();
+        assert_eq!(err, ToArrayError::TooShortAt { got: 2, expected: 3, offset: 51 });
+
+        // non-chunk methods still report a plain TooShort
+        assert_eq!((0..2).take_array::<3>(), Err(ToArrayError::TooShort(2, 3)));
+    }
+
+    #[test]
+    fn into_array() {
+        assert_eq!(vec![1, 2, 3].into_array::<3>(), Ok([1, 2, 3]));
+        assert_eq!(vec![1, 2, 3].into_array::<4>(), Err(ToArrayError::TooShort(3, 4)));
+        assert_eq!([1, 2, 3, 4].into_array::<4>(), Ok([1, 2, 3, 4]));
+        assert_eq!((0..5).into_array::<4>(), Err(ToArrayError::TooLong(4)));
+
+        let set: std::collections::HashSet<_> = (0..4).collect();
+        let mut arr = set.into_array::<4>().unwrap();
+        arr.sort();
+        assert_eq!(arr, [0, 1, 2, 3]);
     }
 }